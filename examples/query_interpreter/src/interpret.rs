@@ -6,11 +6,11 @@ use bevy::reflect::erased_serde::__private::serde::de::DeserializeSeed;
 use bevy::reflect::serde::TypedReflectDeserializer;
 use bevy::reflect::{ParsedPath, Reflect, TypeRegistryInternal as TypeRegistry};
 use bevy_mod_dynamic_query::builder::NamedDynamicBuilder;
+use bevy_mod_dynamic_query::parse::QueryStr;
 use bevy_mod_dynamic_query::pretty_print::DynShow;
 use bevy_mod_dynamic_query::{DynamicItem, DynamicQuery};
 
 use crate::eval_parser::{Expr, Operator as Op, Update};
-use crate::query_parser::QueryStr;
 
 fn get_ron(reg: &TypeRegistry, type_id: TypeId, input: &str) -> Option<Box<dyn Reflect>> {
     use ron::de::Deserializer as Ronzer;
@@ -111,12 +111,7 @@ impl Interpreter {
         comps: &Components,
     ) -> Option<()> {
         let mut from_names = NamedDynamicBuilder::new(reg, comps);
-        for fetch in &query.fetches {
-            fetch.build(&mut from_names);
-        }
-        for conjunction in &query.filters {
-            conjunction.build(&mut from_names);
-        }
+        query.build(&mut from_names);
         self.query = Some(from_names.build()?);
         self.submit = true;
         Some(())
@@ -127,18 +122,12 @@ impl Interpreter {
         for (i, mut items) in state.iter_mut(world).enumerate() {
             info!("---> ({i}) {:?}", DynShow::new(&items));
 
-            let items = items.iter_mut().filter_map(|item| match item {
-                DynamicItem::Entity(_) => None,
-                DynamicItem::Read(_) => None,
-                DynamicItem::Mut(value) => Some(value),
-                DynamicItem::OptionRead(_) => None,
-                DynamicItem::OptionMut(value) => value.as_mut(),
-            });
+            let items = items.iter_mut().filter_map(DynamicItem::as_reflect_mut);
             let zipped = items.zip(self.eval.iter().cycle());
 
             for (value, update) in zipped {
                 info!("(i) updating: {update:?}");
-                update.apply(*value, reg);
+                update.apply(value, reg);
             }
         }
     }
@@ -5,13 +5,12 @@ use bevy_simple_text_input::{
 
 use eval_parser::expressions;
 use interpret::{interpret_submitted_query, Interpreter};
-use query_parser::query;
+use bevy_mod_dynamic_query::parse::QueryStr;
 use winnow::Parser;
 
 mod bevy_simple_text_input;
 mod eval_parser;
 mod interpret;
-mod query_parser;
 mod spawn_scene;
 
 const EXPR_INPUT: Color = Color::YELLOW;
@@ -26,7 +25,7 @@ trait CheckInputField {
 impl CheckInputField for QueryInputField {
     const SUBMIT: bool = true;
     fn validate(value: &str) -> bool {
-        let result = query.parse(value);
+        let result = QueryStr::parse(value);
         if let Err(err) = &result {
             error!("query input:\n{err}");
         }
@@ -185,7 +184,7 @@ fn submit_query(
             debug!("Not correct expression: {update}");
             continue;
         };
-        let query_str = query.parse(&event.value).unwrap();
+        let query_str = QueryStr::parse(&event.value).unwrap();
         info!("{query_str:?}");
         interpreter.set_update(dbg!(update)).unwrap();
         interpreter.set_query(query_str, &reg.read(), comps);
@@ -0,0 +1,95 @@
+//! Compute bevy's [`Access`] for [`DynamicState`](crate::DynamicState)s, so
+//! that the scheduler can tell which systems conflict.
+use bevy_ecs::archetype::{Archetype, ArchetypeComponentId};
+use bevy_ecs::component::ComponentId;
+use bevy_ecs::query::{Access, FilteredAccess};
+
+use crate::builder::Fetch;
+use crate::fetches::Fetches;
+use crate::filters::{FilterKind, Filters};
+
+/// The [`FilteredAccess`] a query with `fetches` and `filters` would have.
+///
+/// This mirrors what bevy does for `Query<Q, Or<(…)>>`, so that two
+/// [`DynamicState`](crate::DynamicState) mutably accessing the same component
+/// are compatible if their `With`/`Without` filters prove them disjoint.
+pub(crate) fn component_access(
+    fetches: &Fetches,
+    filters: &Filters,
+) -> FilteredAccess<ComponentId> {
+    let mut access = FilteredAccess::default();
+    fetches
+        .ids(Fetch::READ_IDX)
+        .for_each(|id| access.add_read(id));
+    fetches
+        .ids(Fetch::MUT_IDX)
+        .for_each(|id| access.add_write(id));
+    // `Option` fetches do not restrict the set of matched entities.
+    let opt_reads = fetches.ids(Fetch::OPTION_READ_IDX);
+    let opt_writes = fetches.ids(Fetch::OPTION_MUT_IDX);
+    opt_reads.for_each(|id| access.access_mut().add_read(id));
+    opt_writes.for_each(|id| access.access_mut().add_write(id));
+
+    let mut or_access: Option<FilteredAccess<ComponentId>> = None;
+    for conjunction in filters.conjunctions() {
+        let mut and_access = access.clone();
+        for filter in conjunction.filters {
+            match filter.kind() {
+                FilterKind::With => and_access.and_with(filter.id()),
+                FilterKind::Without => and_access.and_without(filter.id()),
+                FilterKind::Changed | FilterKind::Added => and_access.add_read(filter.id()),
+            }
+        }
+        for predicate in conjunction.predicates {
+            and_access.add_read(predicate.id());
+        }
+        for custom in conjunction.customs {
+            custom
+                .reads()
+                .iter()
+                .for_each(|id| and_access.add_read(*id));
+        }
+        match &mut or_access {
+            None => or_access = Some(and_access),
+            Some(or_access) => {
+                or_access.append_or(&and_access);
+                or_access.extend_access(&and_access);
+            }
+        }
+    }
+    or_access.unwrap_or(access)
+}
+
+/// Add to `access` the [`ArchetypeComponentId`]s a query with `fetches` and
+/// `filters` reads or writes in `archetype`.
+///
+/// This assumes `archetype` is matched by the query.
+pub(crate) fn add_archetype_access(
+    fetches: &Fetches,
+    filters: &Filters,
+    archetype: &Archetype,
+    access: &mut Access<ArchetypeComponentId>,
+) {
+    let in_archetype = |id| archetype.get_archetype_component_id(id);
+
+    let reads = fetches.ids(Fetch::READ_IDX);
+    let opt_reads = fetches.ids(Fetch::OPTION_READ_IDX);
+    let tick_filters = filters.conjunctions().flat_map(|c| c.filters.iter());
+    let tick_filters = tick_filters
+        .filter(|f| matches!(f.kind(), FilterKind::Changed | FilterKind::Added))
+        .map(|f| f.id());
+    let predicates = filters.conjunctions().flat_map(|c| c.predicates.iter());
+    let predicates = predicates.map(|p| p.id());
+    let customs = filters.conjunctions().flat_map(|c| c.customs.iter());
+    let predicates = predicates.chain(customs.flat_map(|c| c.reads().iter().copied()));
+    let reads = reads.chain(opt_reads).chain(tick_filters).chain(predicates);
+    reads
+        .filter_map(in_archetype)
+        .for_each(|id| access.add_read(id));
+
+    let writes = fetches.ids(Fetch::MUT_IDX);
+    let writes = writes.chain(fetches.ids(Fetch::OPTION_MUT_IDX));
+    writes
+        .filter_map(in_archetype)
+        .for_each(|id| access.add_write(id));
+}
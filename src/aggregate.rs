@@ -0,0 +1,315 @@
+//! Aggregate values of a reflected field over the entities of a [`DynamicState`].
+//!
+//! See [`DynamicState::aggregate`] and [`DynamicState::aggregate_grouped`].
+use std::cmp::Ordering;
+
+use bevy_ecs::world::World;
+use bevy_reflect::Reflect;
+use bevy_utils::HashMap;
+use thiserror::Error;
+
+use crate::field::{FieldError, FieldPath};
+use crate::iter::MatchedEntities;
+use crate::predicate::reflect_partial_cmp;
+use crate::{DynamicState, Ticks};
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum AggregateError {
+    #[error(transparent)]
+    Field(#[from] FieldError),
+    #[error("'{0}' is not a number, it can't be aggregated.")]
+    NotNumeric(String),
+    #[error(
+        "'{path}' is a {first} in some entities, and a {other} in others, it can't be aggregated."
+    )]
+    MixedTypes {
+        path: String,
+        first: String,
+        other: String,
+    },
+}
+
+/// An aggregation over the values of a field, see [`DynamicState::aggregate`].
+///
+/// Paths are the name of a fetched component, followed by a reflect path
+/// within it, such as `Health.current`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate<'a> {
+    /// The number of matched entities, as a `usize`.
+    Count,
+    /// The sum of the field, of the same type as the field.
+    ///
+    /// Integers saturate instead of overflowing.
+    Sum(&'a str),
+    /// The smallest value of the field.
+    Min(&'a str),
+    /// The largest value of the field.
+    Max(&'a str),
+    /// The arithmetic mean of the field, as a `f64`.
+    Mean(&'a str),
+}
+impl<'a> Aggregate<'a> {
+    fn path(&self) -> Option<&'a str> {
+        match self {
+            Aggregate::Count => None,
+            Aggregate::Sum(path)
+            | Aggregate::Min(path)
+            | Aggregate::Max(path)
+            | Aggregate::Mean(path) => Some(path),
+        }
+    }
+}
+
+/// The result of an [`Aggregate`] over the entities with the same `key`.
+#[derive(Debug)]
+pub struct AggregateGroup {
+    /// The value of the field the entities are grouped by.
+    pub key: Box<dyn Reflect>,
+    /// The aggregated value, `None` if no entity in the group has the field.
+    pub value: Option<Box<dyn Reflect>>,
+}
+
+macro_rules! numeric {
+    ($value:expr, |$n:ident| $body:expr, $($ty:ty),*) => {{
+        let value: &dyn Reflect = $value;
+        $( if let Some(&$n) = value.downcast_ref::<$ty>() { Some($body) } else )*
+        { None }
+    }};
+}
+fn to_f64(value: &dyn Reflect) -> Option<f64> {
+    numeric!(
+        value,
+        |n| n as f64,
+        u8,
+        u16,
+        u32,
+        u64,
+        u128,
+        usize,
+        i8,
+        i16,
+        i32,
+        i64,
+        i128,
+        isize,
+        f32,
+        f64
+    )
+}
+/// Add `value` to `sum`, `None` if they are not numbers of the same type.
+fn add(sum: &mut dyn Reflect, value: &dyn Reflect) -> Option<()> {
+    macro_rules! add {
+        (|$l:ident, $r:ident| $add:expr, $($ty:ty),*) => {$(
+            let sum_value = sum.downcast_mut::<$ty>();
+            if let (Some($l), Some(&$r)) = (sum_value, value.downcast_ref::<$ty>()) {
+                *$l = $add;
+                return Some(());
+            }
+        )*};
+    }
+    add!(|l, r| *l + r, f32, f64);
+    add!(
+        |l, r| l.saturating_add(r),
+        u8,
+        u16,
+        u32,
+        u64,
+        u128,
+        usize,
+        i8,
+        i16,
+        i32,
+        i64,
+        i128,
+        isize
+    );
+    None
+}
+
+/// Why a value can't be added to an [`Accumulator`].
+enum PushError {
+    NotNumeric,
+    MixedTypes { first: String, other: String },
+}
+impl PushError {
+    fn at(self, aggregate: Aggregate) -> AggregateError {
+        let path = aggregate.path().unwrap_or_default().to_owned();
+        match self {
+            PushError::NotNumeric => AggregateError::NotNumeric(path),
+            PushError::MixedTypes { first, other } => {
+                AggregateError::MixedTypes { path, first, other }
+            }
+        }
+    }
+}
+
+/// The running state of an [`Aggregate`].
+enum Accumulator {
+    Count(usize),
+    Sum(Option<Box<dyn Reflect>>),
+    Min(Option<Box<dyn Reflect>>),
+    Max(Option<Box<dyn Reflect>>),
+    Mean { sum: f64, count: usize },
+}
+impl Accumulator {
+    fn new(aggregate: Aggregate) -> Self {
+        match aggregate {
+            Aggregate::Count => Accumulator::Count(0),
+            Aggregate::Sum(_) => Accumulator::Sum(None),
+            Aggregate::Min(_) => Accumulator::Min(None),
+            Aggregate::Max(_) => Accumulator::Max(None),
+            Aggregate::Mean(_) => Accumulator::Mean { sum: 0.0, count: 0 },
+        }
+    }
+    /// Add the value of an entity.
+    ///
+    /// `value` is always `None` for [`Aggregate::Count`].
+    fn push(&mut self, value: Option<&dyn Reflect>) -> Result<(), PushError> {
+        let keep = |current: &dyn Reflect, value, ordering| {
+            reflect_partial_cmp(value, current) == Some(ordering)
+        };
+        match (self, value) {
+            (Accumulator::Count(count), _) => *count += 1,
+            (_, None) => {}
+            (_, Some(value)) if to_f64(value).is_none() => return Err(PushError::NotNumeric),
+            (Accumulator::Sum(sum @ None), Some(value))
+            | (Accumulator::Min(sum @ None), Some(value))
+            | (Accumulator::Max(sum @ None), Some(value)) => *sum = Some(value.clone_value()),
+            (Accumulator::Sum(Some(current)), Some(value))
+            | (Accumulator::Min(Some(current)), Some(value))
+            | (Accumulator::Max(Some(current)), Some(value))
+                if current.as_any().type_id() != value.as_any().type_id() =>
+            {
+                let first = current.type_name().to_owned();
+                let other = value.type_name().to_owned();
+                return Err(PushError::MixedTypes { first, other });
+            }
+            (Accumulator::Sum(Some(sum)), Some(value)) => {
+                // Both are numbers of the same type, so this always adds them.
+                add(sum.as_mut(), value);
+            }
+            (Accumulator::Min(Some(min)), Some(value)) => {
+                if keep(min.as_ref(), value, Ordering::Less) {
+                    *min = value.clone_value();
+                }
+            }
+            (Accumulator::Max(Some(max)), Some(value)) => {
+                if keep(max.as_ref(), value, Ordering::Greater) {
+                    *max = value.clone_value();
+                }
+            }
+            (Accumulator::Mean { sum, count }, Some(value)) => {
+                *sum += to_f64(value).ok_or(PushError::NotNumeric)?;
+                *count += 1;
+            }
+        }
+        Ok(())
+    }
+    fn finish(self) -> Option<Box<dyn Reflect>> {
+        match self {
+            Accumulator::Count(count) => Some(Box::new(count)),
+            Accumulator::Sum(value) | Accumulator::Min(value) | Accumulator::Max(value) => value,
+            Accumulator::Mean { count: 0, .. } => None,
+            Accumulator::Mean { sum, count } => Some(Box::new(sum / count as f64)),
+        }
+    }
+}
+
+impl DynamicState {
+    /// Aggregate the values of a field over all entities matching this state.
+    ///
+    /// Fields must be numbers: `f32`, `f64` or any primitive integer. Entities
+    /// without the field's component are skipped. [`Aggregate::Mean`] accepts
+    /// numbers of different types, other aggregates need all values to be of
+    /// the same type.
+    ///
+    /// Returns `None` if no entity has the field, except for
+    /// [`Aggregate::Count`], which always returns a `usize`.
+    ///
+    /// # Errors
+    /// If the path doesn't point to a fetched component or a field of one, if
+    /// the field is not a number, or if it is numbers of different types.
+    pub fn aggregate(
+        &mut self,
+        world: &World,
+        aggregate: Aggregate,
+    ) -> Result<Option<Box<dyn Reflect>>, AggregateError> {
+        let field = aggregate.path();
+        let field = field.map(|path| FieldPath::new(&self.fetches, world.components(), path));
+        let field = field.transpose()?;
+
+        let world = world.as_unsafe_world_cell_readonly();
+        // SAFETY: we have read access to the whole world.
+        let entities = unsafe { MatchedEntities::new(world, self, Ticks::of_world(world)) };
+        let mut accumulator = Accumulator::new(aggregate);
+        for entity in entities {
+            // SAFETY: we have read access to the whole world.
+            let value = field
+                .as_ref()
+                .and_then(|field| unsafe { field.get(entity) });
+            accumulator.push(value).map_err(|e| e.at(aggregate))?;
+        }
+        Ok(accumulator.finish())
+    }
+    /// Like [`Self::aggregate`], but separately for each distinct value of
+    /// the `group_by` field.
+    ///
+    /// Groups are returned in the order their key was first seen. Entities
+    /// without the `group_by` field are skipped. Keys are compared with
+    /// [`Reflect::reflect_partial_eq`], so `group_by` can be any reflected
+    /// value, not only numbers.
+    ///
+    /// Groups are looked up by [`Reflect::reflect_hash`], keys without a hash
+    /// are compared with each group of unhashable keys in turn.
+    ///
+    /// # Errors
+    /// Same as [`Self::aggregate`], `group_by` must also be a valid path.
+    pub fn aggregate_grouped(
+        &mut self,
+        world: &World,
+        aggregate: Aggregate,
+        group_by: &str,
+    ) -> Result<Vec<AggregateGroup>, AggregateError> {
+        let field = aggregate.path();
+        let field = field.map(|path| FieldPath::new(&self.fetches, world.components(), path));
+        let field = field.transpose()?;
+        let group_by = FieldPath::new(&self.fetches, world.components(), group_by)?;
+
+        let world = world.as_unsafe_world_cell_readonly();
+        // SAFETY: we have read access to the whole world.
+        let entities = unsafe { MatchedEntities::new(world, self, Ticks::of_world(world)) };
+        let mut groups: Vec<(Box<dyn Reflect>, Accumulator)> = Vec::new();
+        // Indices in `groups`, by key hash. Several keys may share a hash.
+        let mut hashed: HashMap<u64, Vec<usize>> = HashMap::default();
+        let mut unhashable: Vec<usize> = Vec::new();
+        for entity in entities {
+            // SAFETY: we have read access to the whole world.
+            let Some(key) = (unsafe { group_by.get(entity) }) else {
+                continue;
+            };
+            let candidates = match key.reflect_hash() {
+                Some(hash) => hashed.entry(hash).or_default(),
+                None => &mut unhashable,
+            };
+            let same_key = |i: &&usize| groups[**i].0.reflect_partial_eq(key).unwrap_or(false);
+            let index = match candidates.iter().find(same_key) {
+                Some(index) => *index,
+                None => {
+                    candidates.push(groups.len());
+                    groups.push((key.clone_value(), Accumulator::new(aggregate)));
+                    groups.len() - 1
+                }
+            };
+            let accumulator = &mut groups[index].1;
+            // SAFETY: we have read access to the whole world.
+            let value = field
+                .as_ref()
+                .and_then(|field| unsafe { field.get(entity) });
+            accumulator.push(value).map_err(|e| e.at(aggregate))?;
+        }
+        let groups = groups
+            .into_iter()
+            .map(|(key, accumulator)| AggregateGroup { key, value: accumulator.finish() });
+        Ok(groups.collect())
+    }
+}
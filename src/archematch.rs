@@ -4,16 +4,19 @@ use bevy_ecs::{
 };
 use datazoo::bitset::{Bitset, Ones};
 
+use std::iter;
+
 use crate::{
+    builder::Fetch,
     fetches::Fetches,
-    filters::{Conjunction, Conjunctions, Filters},
-    Ticks,
+    filters::{Conjunctions, Filter, FilterKind, Filters},
+    ArchetypeIndex, Ticks,
 };
 
 fn u32_to_archetype_id(u32: u32) -> ArchetypeId {
     unsafe { std::mem::transmute(u32) }
 }
-fn archetype_id_to_u32(id: ArchetypeId) -> u32 {
+pub(crate) fn archetype_id_to_u32(id: ArchetypeId) -> u32 {
     // SAFETY: ArchetypeId is repr(transparent) u32
     unsafe { std::mem::transmute(id) }
 }
@@ -21,38 +24,49 @@ fn archetype_id_to_u32(id: ArchetypeId) -> u32 {
 #[derive(Clone, Debug, Default)]
 pub(crate) struct MatchedArchetypes {
     ids: Bitset<Box<[u32]>>,
+    /// Archetypes only matched by conjunctions with tick filters or predicates,
+    /// so their entities need to be checked individually.
+    //
     // A cool trick here is that if `tick_match` has zero matches, the `Box` stores in `len`
     // reference metadata that it has size 0.
     // Which avoid an indirection when checking if we have tick_matches.
     tick_matches: Bitset<Box<[u32]>>,
+    /// For each conjunction with tick filters or predicates, the archetypes it matches.
+    ///
+    /// Empty for other conjunctions. This avoids checking
+    /// which conjunction matches the archetype of each iterated entity.
+    conjunction_matches: Box<[Bitset<Vec<u32>>]>,
     last_gen: usize,
 }
 impl MatchedArchetypes {
-    pub(crate) fn new(fetches: &Fetches, filters: &Filters, archetypes: &Archetypes) -> Self {
-        let mut this = MatchedArchetypes::default();
-        this.add_archetypes(fetches, filters, archetypes);
-        this
-    }
     pub(crate) fn add_archetypes(
         &mut self,
         fetches: &Fetches,
         filters: &Filters,
         archetypes: &Archetypes,
     ) {
+        self.init_conjunctions(filters);
         // TODO(perf): check this actually skips things.
         for archetype in archetypes.iter().skip(self.last_gen) {
+            let id = archetype_id_to_u32(archetype.id()) as usize;
             let (matches, ticks) = if filters.is_empty() {
                 (fetches.all_included(archetype.components()), false)
             } else {
-                let match_and_tick = |(mut matches, mut tick), conj: Conjunction| {
+                let (mut matches, mut untick_matches) = (false, false);
+                let conjunctions = filters
+                    .conjunctions()
+                    .zip(self.conjunction_matches.iter_mut());
+                for (conj, conj_matches) in conjunctions {
                     let included = conj.includes(fetches, archetype);
                     matches |= included;
-                    tick |= included & conj.has_tick_filter();
-                    (matches, tick)
-                };
-                filters.conjunctions().fold((false, false), match_and_tick)
+                    match (included, conj.has_entity_filter()) {
+                        (true, true) => conj_matches.enable_bit_extending(id),
+                        (true, false) => untick_matches = true,
+                        (false, _) => {}
+                    }
+                }
+                (matches, matches & !untick_matches)
             };
-            let id = archetype_id_to_u32(archetype.id()) as usize;
             if matches {
                 self.ids.enable_bit_extending(id);
             }
@@ -62,6 +76,98 @@ impl MatchedArchetypes {
         }
         self.last_gen = archetypes.len();
     }
+    /// Same as [`Self::add_archetypes`], but using `index` to find matching
+    /// archetypes rather than checking each archetype's components.
+    ///
+    /// This also matches the archetypes already checked.
+    pub(crate) fn add_archetypes_indexed(
+        &mut self,
+        fetches: &Fetches,
+        filters: &Filters,
+        archetypes: &Archetypes,
+        index: &ArchetypeIndex,
+    ) {
+        self.init_conjunctions(filters);
+        let fetched = || {
+            fetches
+                .ids(Fetch::READ_IDX)
+                .chain(fetches.ids(Fetch::MUT_IDX))
+        };
+        let (ids, ticks) = if filters.is_empty() {
+            (index.matching(fetched(), iter::empty()), Bitset(Vec::new()))
+        } else {
+            let mut ids = Bitset(Vec::new());
+            let mut untick_ids = Bitset(Vec::new());
+            let conjunctions = filters
+                .conjunctions()
+                .zip(self.conjunction_matches.iter_mut());
+            for (conjunction, conj_matches) in conjunctions {
+                let is_without = |f: &&Filter| f.kind() == FilterKind::Without;
+                let (excluded, included): (Vec<&Filter>, Vec<&Filter>) =
+                    conjunction.filters.iter().partition(is_without);
+                let predicates = conjunction.predicates.iter().map(|p| p.id());
+                let required = fetched().chain(included.iter().map(|f| f.id()));
+                let required = required.chain(predicates);
+                let mut matching = index.matching(required, excluded.iter().map(|f| f.id()));
+                if !conjunction.customs.is_empty() {
+                    let rejected = matching.ones().filter(|id| {
+                        let archetype = archetypes.get(u32_to_archetype_id(*id));
+                        !archetype.is_some_and(|a| conjunction.customs_match(a))
+                    });
+                    let rejected: Vec<_> = rejected.collect();
+                    rejected.into_iter().for_each(|id| {
+                        matching.disable_bit(id as usize);
+                    });
+                }
+                union(&mut ids, &matching);
+                if conjunction.has_entity_filter() {
+                    *conj_matches = matching;
+                } else {
+                    union(&mut untick_ids, &matching);
+                }
+            }
+            let mut ticks = ids.clone();
+            let untick = |i| untick_ids.0.get(i).copied().unwrap_or(0);
+            ticks
+                .0
+                .iter_mut()
+                .enumerate()
+                .for_each(|(i, block)| *block &= !untick(i));
+            (ids, ticks)
+        };
+        self.ids = Bitset(ids.0.into_boxed_slice());
+        // Keep the zero-sized `Box` when no archetypes have tick filters.
+        let has_ticks = ticks.0.iter().any(|block| *block != 0);
+        self.tick_matches = Bitset(if has_ticks { ticks.0.into() } else { Box::new([]) });
+        self.last_gen = index.len();
+    }
+    fn init_conjunctions(&mut self, filters: &Filters) {
+        if self.conjunction_matches.is_empty() {
+            let conjunction_count = filters.conjunctions().count();
+            self.conjunction_matches = vec![Bitset::default(); conjunction_count].into();
+        }
+    }
+    /// How many archetypes were checked so far.
+    pub(crate) fn last_gen(&self) -> usize {
+        self.last_gen
+    }
+    pub(crate) fn contains(&self, id: ArchetypeId) -> bool {
+        self.ids.bit(archetype_id_to_u32(id) as usize)
+    }
+    /// Whether some matched archetypes have entities that don't match.
+    #[cfg(feature = "rand")]
+    pub(crate) fn has_entity_checks(&self) -> bool {
+        self.tick_matches.0.iter().any(|block| *block != 0)
+    }
+}
+fn union(set: &mut Bitset<Vec<u32>>, other: &Bitset<Vec<u32>>) {
+    if set.0.len() < other.0.len() {
+        set.0.resize(other.0.len(), 0);
+    }
+    set.0
+        .iter_mut()
+        .zip(&other.0)
+        .for_each(|(block, other)| *block |= other);
 }
 impl MatchedArchetypes {
     #[inline]
@@ -69,6 +175,7 @@ impl MatchedArchetypes {
         ArchematchGet {
             ids: Bitset(&self.ids.0),
             tick_matches: Bitset(&self.tick_matches.0),
+            conjunction_matches: &self.conjunction_matches,
             filters,
         }
     }
@@ -77,6 +184,7 @@ impl MatchedArchetypes {
         ArchematchIter {
             ids: self.ids.ones(),
             tick_matches: Bitset(&self.tick_matches.0),
+            conjunction_matches: &self.conjunction_matches,
             filters: filters.conjunctions(),
         }
     }
@@ -85,6 +193,7 @@ impl MatchedArchetypes {
 pub(crate) struct ArchematchGet<'a> {
     ids: Bitset<&'a [u32]>,
     tick_matches: Bitset<&'a [u32]>,
+    conjunction_matches: &'a [Bitset<Vec<u32>>],
     filters: &'a Filters,
 }
 impl<'a> ArchematchGet<'a> {
@@ -102,18 +211,31 @@ impl<'a> ArchematchGet<'a> {
             return true;
         }
         let conjunctions = self.filters.conjunctions();
-        let mut conjunctions = conjunctions
-            .filter(|c| c.has_tick_filter())
-            .filter(|c| c.in_filter(archetype));
-
-        conjunctions.any(|c| c.within_tick(ticks, entity))
+        within_any_tick(conjunctions, self.conjunction_matches, id, ticks, entity)
     }
 }
+/// Whether `entity` passes the tick filters and predicates of any conjunction matching
+/// the `archetype_id` archetype.
+fn within_any_tick(
+    conjunctions: Conjunctions,
+    conjunction_matches: &[Bitset<Vec<u32>>],
+    archetype_id: usize,
+    ticks: Ticks,
+    entity: UnsafeEntityCell,
+) -> bool {
+    let mut conjunctions = conjunctions
+        .zip(conjunction_matches)
+        .filter(|(_, matches)| matches.bit(archetype_id));
+
+    // SAFETY: the query has read access to the components of its predicates.
+    conjunctions.any(|(c, _)| unsafe { c.within_entity(ticks, entity) })
+}
 #[derive(Default)]
 enum CheckTickInner {
     #[default]
     None,
-    Filter,
+    /// Check the tick filters of conjunctions matching this archetype.
+    Filter(usize),
 }
 #[derive(Default)]
 pub(crate) struct CheckTick(CheckTickInner);
@@ -127,15 +249,9 @@ impl CheckTick {
     ) -> bool {
         match self.0 {
             CheckTickInner::None => true,
-            CheckTickInner::Filter => {
-                let conjs = iter.filters.clone();
-                let archetype = entity.archetype();
-
-                let mut conjunctions = conjs
-                    .filter(|c| c.has_tick_filter())
-                    .filter(|c| c.in_filter(archetype));
-
-                conjunctions.any(|c| c.within_tick(ticks, entity))
+            CheckTickInner::Filter(id) => {
+                let conjunctions = iter.filters.clone();
+                within_any_tick(conjunctions, iter.conjunction_matches, id, ticks, entity)
             }
         }
     }
@@ -143,6 +259,7 @@ impl CheckTick {
 pub(crate) struct ArchematchIter<'a> {
     ids: Ones<'a>,
     tick_matches: Bitset<&'a [u32]>,
+    conjunction_matches: &'a [Bitset<Vec<u32>>],
     filters: Conjunctions<'a>,
 }
 impl<'a> Iterator for ArchematchIter<'a> {
@@ -152,7 +269,7 @@ impl<'a> Iterator for ArchematchIter<'a> {
         let next = self.ids.next()?;
 
         let check = if self.tick_matches.bit(next as usize) {
-            CheckTick(CheckTickInner::Filter)
+            CheckTick(CheckTickInner::Filter(next as usize))
         } else {
             CheckTick(CheckTickInner::None)
         };
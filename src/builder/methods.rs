@@ -3,8 +3,10 @@ use bevy_ecs::{
     prelude::{Component, World},
     reflect::AppTypeRegistry,
 };
-use bevy_reflect::ReflectFromPtr;
+use bevy_reflect::{Reflect, ReflectFromPtr};
 
+use crate::dynamic_filter::DynamicFilter;
+use crate::predicate::{CompareOp, Predicate};
 use crate::DynamicQuery;
 
 use super::{AndFilter, AndFilters, Fetch, FetchData, OrFilters};
@@ -134,4 +136,29 @@ impl<'w> OrBuilder<'w> {
         self.filters.0.push(AndFilter::Changed(id));
         self
     }
+
+    /// Only entities where the field at `path` of `T` compares to `value` with `op`.
+    ///
+    /// # Panics
+    /// If `path` is not a valid reflect path.
+    pub fn predicate<T: Component>(
+        &mut self,
+        path: &str,
+        op: CompareOp,
+        value: impl Reflect,
+    ) -> &mut Self {
+        let data = with_info::<T>(self.world);
+        let predicate = Predicate::new(data, path, op, Box::new(value)).unwrap();
+        self.predicate_by_id(predicate)
+    }
+
+    pub fn predicate_by_id(&mut self, predicate: Predicate) -> &mut Self {
+        self.filters.0.push(AndFilter::Predicate(predicate));
+        self
+    }
+
+    pub fn custom(&mut self, filter: impl DynamicFilter) -> &mut Self {
+        self.filters.0.push(AndFilter::Custom(Box::new(filter)));
+        self
+    }
 }
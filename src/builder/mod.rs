@@ -4,22 +4,30 @@ use bevy_ecs::query::{ReadOnlyWorldQuery, WorldQuery};
 use bevy_ecs::{component::ComponentId, prelude::Query, world::World};
 use bevy_reflect::ReflectFromPtr;
 
+use crate::dynamic_filter::DynamicFilter;
+use crate::predicate::Predicate;
 use crate::DynamicQuery;
 
 pub use methods::DynamicQueryBuilder;
 pub use named::{NamedDynamicBuilder, NamedOrBuilder};
+pub use resources::{NamedResourcesBuilder, NamedResourcesError};
 pub use traits::{DFetches, DOr, DQuery};
 
 mod methods;
 mod named;
+mod resources;
 mod traits;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub enum AndFilter {
     With(ComponentId),
     Without(ComponentId),
     Changed(ComponentId),
     Added(ComponentId),
+    /// Only entities with a component value satisfying the [`Predicate`].
+    Predicate(Predicate),
+    /// Only entities matching a user-defined [`DynamicFilter`].
+    Custom(Box<dyn DynamicFilter>),
 }
 #[derive(Clone, Debug)]
 pub struct AndFilters(pub Vec<AndFilter>);
@@ -45,6 +53,15 @@ pub enum Fetch {
     OptionMut(FetchData),
     Entity,
 }
+/// The kind of a [`Fetch`] or [`DynamicItem`](crate::DynamicItem), without its data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FetchKind {
+    Read,
+    Mut,
+    OptionRead,
+    OptionMut,
+    Entity,
+}
 impl Fetch {
     // SAFETY: !!!!!IMPORTANT!!!! Make sure this is in the same order as
     // the enum variants in `Fetch`.
@@ -63,6 +80,15 @@ impl Fetch {
             Fetch::Entity => Fetch::ENTITY_IDX,
         }
     }
+    pub const fn kind(&self) -> FetchKind {
+        match self {
+            Fetch::Read(_) => FetchKind::Read,
+            Fetch::Mut(_) => FetchKind::Mut,
+            Fetch::OptionRead(_) => FetchKind::OptionRead,
+            Fetch::OptionMut(_) => FetchKind::OptionMut,
+            Fetch::Entity => FetchKind::Entity,
+        }
+    }
     pub(crate) fn data(&self) -> &FetchData {
         use Fetch::{Mut, OptionMut, OptionRead, Read};
         match self {
@@ -89,27 +115,27 @@ impl PartialEq for Fetch {
 impl Eq for Fetch {}
 impl PartialOrd for Fetch {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Fetch {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         use std::cmp::Ordering::{Equal, Greater, Less};
         use Fetch::{Mut, OptionMut, OptionRead, Read};
         match (self, other) {
             (Read(left), Read(right))
             | (Mut(left), Mut(right))
             | (OptionRead(left), OptionRead(right))
-            | (OptionMut(left), OptionMut(right)) => left.id.partial_cmp(&right.id),
-            (Fetch::Entity, Fetch::Entity) => Some(Equal),
+            | (OptionMut(left), OptionMut(right)) => left.id.cmp(&right.id),
+            (Fetch::Entity, Fetch::Entity) => Equal,
             (Mut(_), Read(_))
             | (OptionRead(_), Read(_) | Mut(_))
             | (OptionMut(_), Read(_) | Mut(_) | OptionRead(_))
-            | (Fetch::Entity, _) => Some(Greater),
-            _ => Some(Less),
+            | (Fetch::Entity, _) => Greater,
+            _ => Less,
         }
     }
 }
-impl Ord for Fetch {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.partial_cmp(other).unwrap()
-    }
-}
 
 impl<Q, F> DQuery for Query<'_, '_, Q, F>
 where
@@ -2,6 +2,10 @@ use bevy_ecs::component::{ComponentId, Components};
 use bevy_reflect::{ReflectFromPtr, TypeRegistry};
 
 use super::{AndFilter, AndFilters, Fetch, FetchData, OrFilters};
+use crate::dynamic_filter::DynamicFilter;
+#[cfg(feature = "parse")]
+use crate::predicate::CompareOp;
+use crate::predicate::Predicate;
 use crate::DynamicQuery;
 
 pub struct NamedDynamicBuilder<'w> {
@@ -146,4 +150,30 @@ impl<'w> NamedOrBuilder<'w> {
         self.filters.0.push(AndFilter::Changed(id));
         self
     }
+
+    /// Only entities where the field at `path` of `name` compares to the
+    /// RON `literal` with `op`, see [`Predicate::from_literal`].
+    #[cfg(feature = "parse")]
+    pub fn predicate(
+        &mut self,
+        name: impl AsRef<str>,
+        path: &str,
+        op: CompareOp,
+        literal: &str,
+    ) -> &mut Self {
+        let data = with_info(self.reg, self.comps, name);
+        // TODO(err): should return result instead.
+        let predicate = Predicate::from_literal(self.reg, data, path, op, literal).unwrap();
+        self.predicate_by_id(predicate)
+    }
+
+    pub fn predicate_by_id(&mut self, predicate: Predicate) -> &mut Self {
+        self.filters.0.push(AndFilter::Predicate(predicate));
+        self
+    }
+
+    pub fn custom(&mut self, filter: impl DynamicFilter) -> &mut Self {
+        self.filters.0.push(AndFilter::Custom(Box::new(filter)));
+        self
+    }
 }
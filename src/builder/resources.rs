@@ -0,0 +1,112 @@
+use bevy_ecs::component::Components;
+use bevy_reflect::{ReflectFromPtr, TypeRegistry};
+use thiserror::Error;
+
+use super::{Fetch, FetchData};
+use crate::resources::DynamicResources;
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum NamedResourcesError {
+    #[error("Those types are not reflect-registered resources of this world: {0:?}")]
+    Unresolved(Vec<String>),
+    #[error("The type '{0}' is not registered with ReflectFromPtr, it cannot be fetched.")]
+    NotFetchable(String),
+    #[error("The same resource is fetched several times.")]
+    Duplicate,
+}
+
+/// Build a [`DynamicResources`] from resource names.
+///
+/// Like with [`NamedDynamicBuilder`](super::NamedDynamicBuilder), the
+/// resources must already be registered in the world's [`Components`].
+///
+/// Names that can't be resolved are reported by [`Self::build`].
+pub struct NamedResourcesBuilder<'w> {
+    comps: &'w Components,
+    reg: &'w TypeRegistry,
+    fetches: Vec<Fetch>,
+    error: Option<NamedResourcesError>,
+}
+
+fn with_info(
+    registry: &TypeRegistry,
+    comps: &Components,
+    name: &str,
+) -> Result<FetchData, NamedResourcesError> {
+    let unresolved = || NamedResourcesError::Unresolved(vec![name.to_owned()]);
+    let registration = registry.get_with_short_name(name).ok_or_else(unresolved)?;
+    let from_ptr = registration.data::<ReflectFromPtr>();
+    let not_fetchable = || NamedResourcesError::NotFetchable(name.to_owned());
+    let from_ptr = from_ptr.ok_or_else(not_fetchable)?.clone();
+    let id = comps.get_resource_id(registration.type_id());
+    let id = id.ok_or_else(unresolved)?;
+    Ok(FetchData { id, from_ptr })
+}
+
+impl<'w> NamedResourcesBuilder<'w> {
+    pub fn new(reg: &'w TypeRegistry, comps: &'w Components) -> Self {
+        Self { reg, comps, fetches: Vec::new(), error: None }
+    }
+    fn named(&mut self, name: impl AsRef<str>, fetch: fn(FetchData) -> Fetch) -> &mut Self {
+        match (
+            with_info(self.reg, self.comps, name.as_ref()),
+            &mut self.error,
+        ) {
+            (Ok(data), _) => self.fetches.push(fetch(data)),
+            (
+                Err(NamedResourcesError::Unresolved(names)),
+                Some(NamedResourcesError::Unresolved(unresolved)),
+            ) => unresolved.extend(names),
+            (Err(err), error @ None) => *error = Some(err),
+            (Err(_), Some(_)) => {}
+        }
+        self
+    }
+
+    pub fn resource(&mut self, name: impl AsRef<str>) -> &mut Self {
+        self.named(name, Fetch::Read)
+    }
+
+    pub fn resource_mut(&mut self, name: impl AsRef<str>) -> &mut Self {
+        self.named(name, Fetch::Mut)
+    }
+
+    pub fn ref_by_id(&mut self, info: FetchData) -> &mut Self {
+        self.fetches.push(Fetch::Read(info));
+        self
+    }
+
+    pub fn mut_by_id(&mut self, info: FetchData) -> &mut Self {
+        self.fetches.push(Fetch::Mut(info));
+        self
+    }
+
+    pub fn optional(&mut self, name: impl AsRef<str>) -> &mut Self {
+        self.named(name, Fetch::OptionRead)
+    }
+
+    pub fn optional_mut(&mut self, name: impl AsRef<str>) -> &mut Self {
+        self.named(name, Fetch::OptionMut)
+    }
+
+    pub fn optional_ref_by_id(&mut self, info: FetchData) -> &mut Self {
+        self.fetches.push(Fetch::OptionRead(info));
+        self
+    }
+
+    pub fn optional_mut_by_id(&mut self, info: FetchData) -> &mut Self {
+        self.fetches.push(Fetch::OptionMut(info));
+        self
+    }
+
+    /// # Errors
+    /// If a name is not a reflect-registered resource of the world, or if
+    /// the same resource is fetched several times.
+    pub fn build(&mut self) -> Result<DynamicResources, NamedResourcesError> {
+        let fetches = std::mem::take(&mut self.fetches);
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        DynamicResources::new(fetches).ok_or(NamedResourcesError::Duplicate)
+    }
+}
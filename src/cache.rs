@@ -0,0 +1,137 @@
+//! [`DynamicQueryCache`], share [`DynamicState`]s between identical queries.
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use bevy_ecs::archetype::Archetypes;
+use bevy_ecs::system::{ResMut, Resource};
+use bevy_utils::HashMap;
+
+use crate::{ArchetypeIndex, DynamicQuery, DynamicState};
+
+/// Id of the next [`DynamicQueryCache`], so that handles from another cache
+/// are detected.
+static NEXT_CACHE_ID: AtomicU32 = AtomicU32::new(0);
+
+/// A cheap handle to a [`DynamicState`] in a [`DynamicQueryCache`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DynamicQueryHandle {
+    cache: u32,
+    index: u32,
+}
+
+/// A [`Resource`] storing a single [`DynamicState`] per distinct [`DynamicQuery`].
+///
+/// Queries are compared in canonical form, so queries with the same fetches
+/// and filters in a different order share the same state.
+///
+/// New states are matched with archetypes using an [`ArchetypeIndex`] shared
+/// by all states, so adding a query to the cache is cheap.
+///
+/// Add the [`update_dynamic_query_cache`] system to your app, so that states
+/// are updated with new archetypes once per frame.
+///
+/// # Example
+///
+/// ```
+/// use bevy::prelude::*;
+/// use bevy_mod_dynamic_query::{DynamicQuery, DynamicQueryCache};
+///
+/// fn print_count(world: &mut World, query: &DynamicQuery) {
+///     world.resource_scope(|world, mut cache: Mut<DynamicQueryCache>| {
+///         let handle = cache.get_or_insert(query, world.archetypes());
+///         let count = cache.state_mut(handle).iter(world).count();
+///         println!("{count} entities match the query");
+///     });
+/// }
+/// ```
+#[derive(Resource)]
+pub struct DynamicQueryCache {
+    id: u32,
+    handles: HashMap<DynamicQuery, DynamicQueryHandle>,
+    states: Vec<DynamicState>,
+    index: ArchetypeIndex,
+}
+impl Default for DynamicQueryCache {
+    fn default() -> Self {
+        Self {
+            id: NEXT_CACHE_ID.fetch_add(1, Ordering::Relaxed),
+            handles: HashMap::default(),
+            states: Vec::new(),
+            index: ArchetypeIndex::default(),
+        }
+    }
+}
+impl DynamicQueryCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// The index used to match states with archetypes.
+    pub fn index(&self) -> &ArchetypeIndex {
+        &self.index
+    }
+    /// How many distinct queries are cached.
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+    /// The handle of `query`, if it is in the cache.
+    pub fn get(&self, query: &DynamicQuery) -> Option<DynamicQueryHandle> {
+        self.handles.get(query).copied()
+    }
+    /// The handle of `query`, adding its [`DynamicState`] to the cache if not
+    /// already present.
+    ///
+    /// `archetypes` are the archetypes of the world the cache is used with.
+    pub fn get_or_insert(
+        &mut self,
+        query: &DynamicQuery,
+        archetypes: &Archetypes,
+    ) -> DynamicQueryHandle {
+        if let Some(handle) = self.get(query) {
+            return handle;
+        }
+        let handle = DynamicQueryHandle { cache: self.id, index: self.states.len() as u32 };
+        self.index.update(archetypes);
+        let state = DynamicState::new_indexed(query, archetypes, &self.index);
+        self.states.push(state);
+        self.handles.insert(query.clone(), handle);
+        handle
+    }
+    /// The state of `handle`.
+    ///
+    /// # Panics
+    /// If `handle` is from another `DynamicQueryCache`.
+    pub fn state(&self, handle: DynamicQueryHandle) -> &DynamicState {
+        &self.states[self.index_of(handle)]
+    }
+    /// The state of `handle`, mutably, to query the world with it.
+    ///
+    /// # Panics
+    /// If `handle` is from another `DynamicQueryCache`.
+    pub fn state_mut(&mut self, handle: DynamicQueryHandle) -> &mut DynamicState {
+        let index = self.index_of(handle);
+        &mut self.states[index]
+    }
+    fn index_of(&self, handle: DynamicQueryHandle) -> usize {
+        assert_eq!(
+            handle.cache, self.id,
+            "DynamicQueryHandle used with another DynamicQueryCache"
+        );
+        handle.index as usize
+    }
+    /// Add new archetypes in `archetypes` to all cached states.
+    ///
+    /// States already up to date are skipped.
+    pub fn update_archetypes(&mut self, archetypes: &Archetypes) {
+        self.index.update(archetypes);
+        for state in &mut self.states {
+            state.add_archetypes_indexed(archetypes, &self.index);
+        }
+    }
+}
+
+/// Update all [`DynamicQueryCache`] states with new archetypes.
+pub fn update_dynamic_query_cache(mut cache: ResMut<DynamicQueryCache>, archetypes: &Archetypes) {
+    cache.update_archetypes(archetypes);
+}
@@ -0,0 +1,144 @@
+//! Iterate over a [`DynamicState`] in batches, resuming where the previous
+//! batch stopped, for example over several frames.
+use bevy_ecs::prelude::Entity;
+use bevy_ecs::world::unsafe_world_cell::{UnsafeEntityCell, UnsafeWorldCell};
+use bevy_ecs::world::World;
+use bevy_utils::HashSet;
+
+use crate::archematch::archetype_id_to_u32;
+use crate::debug_unchecked::DebugUnchecked;
+use crate::iter::{fetch_buffer, fetch_buffer_ro};
+use crate::{DynamicRow, DynamicState, Ticks};
+
+type FetchBuffer<'w, 's> = fn(&'s DynamicState, UnsafeEntityCell<'w>) -> DynamicRow<'w, 's>;
+
+/// A [`DynamicState`] with a position in its matched entities.
+///
+/// Each call to [`Self::next_batch`] returns at most `n` rows, starting after
+/// the last row of the previous batch.
+///
+/// Entities are visited archetype by archetype, in the order archetypes were
+/// created. Between batches:
+///
+/// - Archetypes created since the previous batch are added to the state, and
+///   visited once the cursor reaches them.
+/// - Despawned entities are not returned.
+/// - Entities spawned in an archetype the cursor is in or hasn't reached yet
+///   are returned.
+/// - Entities that moved to an archetype the cursor hasn't reached yet are
+///   returned again when the cursor reaches it.
+///
+/// Each entity of an archetype is returned at most once per pass, even when
+/// removing other entities reorders the archetype.
+#[derive(Clone, Debug)]
+pub struct DynamicCursor {
+    state: DynamicState,
+    /// Id of the archetype the cursor is in.
+    archetype: u32,
+    /// Entities of `archetype` left to check, the next one last.
+    pending: Vec<Entity>,
+    /// Entities of `archetype` already checked.
+    visited: HashSet<Entity>,
+}
+impl DynamicCursor {
+    pub fn new(state: DynamicState) -> Self {
+        DynamicCursor {
+            state,
+            archetype: 0,
+            pending: Vec::new(),
+            visited: HashSet::new(),
+        }
+    }
+    pub fn state(&self) -> &DynamicState {
+        &self.state
+    }
+    pub fn into_state(self) -> DynamicState {
+        self.state
+    }
+    /// Start over from the first matched entity.
+    pub fn reset(&mut self) {
+        self.archetype = 0;
+        self.pending.clear();
+        self.visited.clear();
+    }
+    /// The next `n` rows of matched entities.
+    ///
+    /// Returns less than `n` rows when the cursor reaches the last matched
+    /// entity. Call [`Self::reset`] to start a new pass.
+    pub fn next_batch<'w, 's>(&'s mut self, world: &'w World, n: usize) -> Vec<DynamicRow<'w, 's>> {
+        let world = world.as_unsafe_world_cell_readonly();
+        // SAFETY: we have read access to the whole world.
+        unsafe { self.batch(world, n, fetch_buffer_ro) }
+    }
+    /// Same as [`Self::next_batch`], but with mutable items.
+    pub fn next_batch_mut<'w, 's>(
+        &'s mut self,
+        world: &'w mut World,
+        n: usize,
+    ) -> Vec<DynamicRow<'w, 's>> {
+        let world = world.as_unsafe_world_cell();
+        // SAFETY: we have exclusive access to the whole world.
+        unsafe { self.batch(world, n, fetch_buffer) }
+    }
+    /// # Safety
+    /// `world` must have the access required by `fetch` to the components
+    /// fetched by the state.
+    unsafe fn batch<'w, 's>(
+        &'s mut self,
+        world: UnsafeWorldCell<'w>,
+        n: usize,
+        fetch: FetchBuffer<'w, 's>,
+    ) -> Vec<DynamicRow<'w, 's>> {
+        let archetypes = world.archetypes();
+        self.state.add_archetypes(archetypes);
+
+        let ticks = Ticks::of_world(world);
+        let DynamicCursor {
+            state,
+            archetype: cursor_archetype,
+            pending,
+            visited,
+        } = self;
+        let state = &*state;
+        let mut batch = Vec::with_capacity(n);
+        let mut ids = state.archetype_ids.iter(&state.filters);
+        while batch.len() < n {
+            let Some((id, check)) = ids.next() else {
+                break;
+            };
+            let index = archetype_id_to_u32(id);
+            if index < *cursor_archetype {
+                continue;
+            }
+            if index > *cursor_archetype {
+                *cursor_archetype = index;
+                pending.clear();
+                visited.clear();
+            }
+            let archetype = unsafe { archetypes.get(id).prod_unchecked_unwrap() };
+            // Snapshot the entities not visited yet, rather than keeping a row:
+            // removing an entity moves the archetype's last entity to its row,
+            // possibly before the cursor.
+            // Snapshot again once it is drained, for entities spawned since.
+            while batch.len() < n {
+                if pending.is_empty() {
+                    let entities = archetype.entities().iter().rev().map(|e| e.entity());
+                    pending.extend(entities.filter(|e| !visited.contains(e)));
+                }
+                let Some(entity) = pending.pop() else {
+                    break;
+                };
+                visited.insert(entity);
+                // Skip entities despawned or moved out of the archetype since
+                // the snapshot.
+                let Some(entity) = world.get_entity(entity) else {
+                    continue;
+                };
+                if entity.archetype().id() == id && check.within_tick(&ids, ticks, entity) {
+                    batch.push(fetch(state, entity));
+                }
+            }
+        }
+        batch
+    }
+}
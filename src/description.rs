@@ -0,0 +1,248 @@
+//! A process-independent description of a [`DynamicQuery`].
+//!
+//! [`DynamicQuery`] stores [`ComponentId`]s and [`ReflectFromPtr`], which are
+//! only valid for a single world in a single process. [`DynamicQueryDescription`]
+//! refers to components by their full type name instead. With the `serde`
+//! feature, it can be serialized, to be stored in save files or mod manifests.
+use bevy_ecs::component::{ComponentId, Components};
+use bevy_ecs::reflect::AppTypeRegistry;
+use bevy_ecs::world::World;
+use bevy_reflect::{ReflectFromPtr, TypeRegistration, TypeRegistry};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::builder::{AndFilter, AndFilters, Fetch, FetchData, OrFilters};
+use crate::filters::FilterKind;
+use crate::predicate::{CompareOp, Predicate};
+use crate::DynamicQuery;
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum ResolveError {
+    #[error("The world has no AppTypeRegistry resource.")]
+    NoRegistry,
+    #[error("Those types are not reflect-registered components: {0:?}")]
+    Unresolved(Vec<String>),
+    #[error("The type '{0}' is not registered with ReflectFromPtr, it cannot be fetched.")]
+    NotFetchable(String),
+    #[error("The query accesses or filters the same component several times.")]
+    Duplicate,
+    #[error("Predicate on '{0}' can't be resolved: {1}")]
+    Predicate(String, String),
+    #[error("The custom filter {0} can't be resolved, it only exists at runtime.")]
+    Custom(String),
+}
+
+/// A [`Fetch`] with the full type name of the fetched component.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum FetchDescription {
+    Read(String),
+    Mut(String),
+    OptionRead(String),
+    OptionMut(String),
+    Entity,
+}
+/// An [`AndFilter`] with the full type name of the filtered component.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum FilterDescription {
+    With(String),
+    Without(String),
+    Changed(String),
+    Added(String),
+    /// A [`Predicate`](crate::Predicate) on the field at `path` of `component`.
+    ///
+    /// `literal` is `None` if the predicate was not created from a literal,
+    /// such descriptions cannot be resolved.
+    Predicate {
+        component: String,
+        path: String,
+        op: CompareOp,
+        literal: Option<String>,
+    },
+    /// A [`DynamicFilter`](crate::DynamicFilter), with its `Debug` representation.
+    ///
+    /// Descriptions with custom filters cannot be resolved.
+    Custom(String),
+}
+
+/// A [`DynamicQuery`] with components referred to by their full type name.
+///
+/// Use [`DynamicQuery::describe`] to create one, and
+/// [`DynamicQueryDescription::resolve`] to get back a [`DynamicQuery`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DynamicQueryDescription {
+    pub fetches: Vec<FetchDescription>,
+    /// The filters, as a list of conjunctions, any of which must match.
+    pub filters: Vec<Vec<FilterDescription>>,
+}
+
+impl DynamicQuery {
+    /// Describe this query in terms of component type names.
+    ///
+    /// # Panics
+    /// If `components` is not from the world this query was created with.
+    pub fn describe(&self, components: &Components) -> DynamicQueryDescription {
+        let name = |id: ComponentId| {
+            let info = components.get_info(id);
+            let info = info.expect("DynamicQuery components should exist in the provided world");
+            info.name().to_owned()
+        };
+        let fetches = &self.fetches;
+        let mut described = Vec::with_capacity(fetches.len());
+        let mut kind = |idx, describe: fn(String) -> FetchDescription| {
+            described.extend(fetches.ids(idx).map(|id| describe(name(id))));
+        };
+        kind(Fetch::READ_IDX, FetchDescription::Read);
+        kind(Fetch::MUT_IDX, FetchDescription::Mut);
+        kind(Fetch::OPTION_READ_IDX, FetchDescription::OptionRead);
+        kind(Fetch::OPTION_MUT_IDX, FetchDescription::OptionMut);
+        if fetches.has_entity {
+            described.push(FetchDescription::Entity);
+        }
+        let filters = self.filters.conjunctions().map(|conjunction| {
+            let filters = conjunction.filters.iter().map(|filter| {
+                let name = name(filter.id());
+                match filter.kind() {
+                    FilterKind::With => FilterDescription::With(name),
+                    FilterKind::Without => FilterDescription::Without(name),
+                    FilterKind::Changed => FilterDescription::Changed(name),
+                    FilterKind::Added => FilterDescription::Added(name),
+                }
+            });
+            let predicates =
+                conjunction
+                    .predicates
+                    .iter()
+                    .map(|predicate| FilterDescription::Predicate {
+                        component: name(predicate.id()),
+                        path: predicate.path().to_string(),
+                        op: predicate.op(),
+                        literal: predicate.literal().map(str::to_owned),
+                    });
+            let customs = conjunction.customs.iter();
+            let customs = customs.map(|c| FilterDescription::Custom(format!("{c:?}")));
+            filters.chain(predicates).chain(customs).collect()
+        });
+        DynamicQueryDescription { fetches: described, filters: filters.collect() }
+    }
+}
+
+impl DynamicQueryDescription {
+    /// Create a [`DynamicQuery`] for `world` matching this description.
+    ///
+    /// This fails if any of the types is not a reflect-registered component
+    /// in `world`, [`ResolveError::Unresolved`] lists all of them.
+    pub fn resolve(&self, world: &World) -> Result<DynamicQuery, ResolveError> {
+        let registry = world.get_resource::<AppTypeRegistry>();
+        let registry = registry.ok_or(ResolveError::NoRegistry)?.read();
+        self.resolve_with(&registry, world.components())
+    }
+    /// Same as [`Self::resolve`], with an explicit registry and components.
+    pub fn resolve_with(
+        &self,
+        registry: &TypeRegistry,
+        components: &Components,
+    ) -> Result<DynamicQuery, ResolveError> {
+        let mut unresolved = Vec::new();
+        let mut id = |name: &String| {
+            let registration = registry.get_with_name(name);
+            let id = registration.and_then(|r| components.get_id(r.type_id()));
+            if id.is_none() {
+                unresolved.push(name.clone());
+            }
+            id.map(|id| (id, registration))
+        };
+        let entity_count = self
+            .fetches
+            .iter()
+            .filter(|f| **f == FetchDescription::Entity);
+        if entity_count.count() > 1 {
+            return Err(ResolveError::Duplicate);
+        }
+        let mut fetches = Vec::with_capacity(self.fetches.len());
+        for fetch in &self.fetches {
+            use FetchDescription::{Entity, Mut, OptionMut, OptionRead, Read};
+            let (Read(name) | Mut(name) | OptionRead(name) | OptionMut(name)) = fetch else {
+                fetches.push(Fetch::Entity);
+                continue;
+            };
+            let Some((id, registration)) = id(name) else {
+                continue;
+            };
+            let from_ptr = registration.and_then(|r| r.data::<ReflectFromPtr>());
+            let from_ptr = from_ptr.ok_or_else(|| ResolveError::NotFetchable(name.clone()))?;
+            let data = FetchData { id, from_ptr: from_ptr.clone() };
+            fetches.push(match fetch {
+                Read(_) => Fetch::Read(data),
+                Mut(_) => Fetch::Mut(data),
+                OptionRead(_) => Fetch::OptionRead(data),
+                OptionMut(_) => Fetch::OptionMut(data),
+                Entity => unreachable!(),
+            });
+        }
+        let mut filters = OrFilters(Vec::with_capacity(self.filters.len()));
+        for conjunction in &self.filters {
+            let mut and_filters = Vec::with_capacity(conjunction.len());
+            for filter in conjunction {
+                use FilterDescription::{Added, Changed, Custom, Predicate, With, Without};
+                let name = match filter {
+                    With(name) | Without(name) | Changed(name) | Added(name) => name,
+                    Predicate { component, .. } => component,
+                    Custom(custom) => return Err(ResolveError::Custom(custom.clone())),
+                };
+                let Some((id, registration)) = id(name) else {
+                    continue;
+                };
+                and_filters.push(match filter {
+                    With(_) => AndFilter::With(id),
+                    Without(_) => AndFilter::Without(id),
+                    Changed(_) => AndFilter::Changed(id),
+                    Added(_) => AndFilter::Added(id),
+                    Custom(_) => unreachable!(),
+                    Predicate { path, op, literal, .. } => {
+                        let predicate =
+                            resolve_predicate(registry, registration, id, path, *op, literal);
+                        let predicate =
+                            predicate.map_err(|e| ResolveError::Predicate(name.clone(), e))?;
+                        AndFilter::Predicate(predicate)
+                    }
+                });
+            }
+            filters.0.push(AndFilters(and_filters));
+        }
+        if !unresolved.is_empty() {
+            return Err(ResolveError::Unresolved(unresolved));
+        }
+        DynamicQuery::new(fetches, filters).ok_or(ResolveError::Duplicate)
+    }
+}
+#[cfg(feature = "parse")]
+fn resolve_predicate(
+    registry: &TypeRegistry,
+    registration: Option<&TypeRegistration>,
+    id: ComponentId,
+    path: &str,
+    op: CompareOp,
+    literal: &Option<String>,
+) -> Result<Predicate, String> {
+    let literal = literal.as_ref().ok_or("it has no literal")?;
+    let from_ptr = registration.and_then(|r| r.data::<ReflectFromPtr>());
+    let from_ptr = from_ptr.ok_or("it is not registered with ReflectFromPtr")?;
+    let component = FetchData { id, from_ptr: from_ptr.clone() };
+    let predicate = Predicate::from_literal(registry, component, path, op, literal);
+    predicate.map_err(|e| e.to_string())
+}
+#[cfg(not(feature = "parse"))]
+fn resolve_predicate(
+    _: &TypeRegistry,
+    _: Option<&TypeRegistration>,
+    _: ComponentId,
+    _: &str,
+    _: CompareOp,
+    _: &Option<String>,
+) -> Result<Predicate, String> {
+    Err("literals can only be parsed with the `parse` feature".to_owned())
+}
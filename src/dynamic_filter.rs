@@ -0,0 +1,153 @@
+//! User-defined filters, for conditions that can't be expressed with
+//! `With`, `Without`, `Changed` and `Added`.
+//!
+//! Implement [`DynamicFilter`] and add it to a conjunction with
+//! [`AndFilter::Custom`](crate::AndFilter::Custom).
+use std::any::{Any, TypeId};
+use std::fmt;
+
+use bevy_ecs::archetype::Archetype;
+use bevy_ecs::component::{Component, ComponentId};
+use bevy_ecs::prelude::Entity;
+use bevy_ecs::ptr::Ptr;
+use bevy_ecs::world::unsafe_world_cell::UnsafeEntityCell;
+
+/// A filter checked first on archetypes, then optionally on each entity of
+/// the matched archetypes.
+///
+/// Component ids are specific to a world, [`crate::DynamicQuery::rebind`]
+/// keeps custom filters as they are.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::HashSet;
+/// use std::sync::{Arc, RwLock};
+/// use bevy::prelude::*;
+/// use bevy_mod_dynamic_query::{DynamicFilter, FilterEntity};
+///
+/// /// Entities in a set shared with the rest of the game.
+/// #[derive(Clone, Debug)]
+/// struct Visible(Arc<RwLock<HashSet<Entity>>>);
+///
+/// impl PartialEq for Visible {
+///     fn eq(&self, other: &Self) -> bool {
+///         Arc::ptr_eq(&self.0, &other.0)
+///     }
+/// }
+///
+/// impl DynamicFilter for Visible {
+///     fn has_entity_check(&self) -> bool {
+///         true
+///     }
+///     fn matches_entity(&self, entity: FilterEntity) -> bool {
+///         self.0.read().unwrap().contains(&entity.id())
+///     }
+/// }
+/// ```
+pub trait DynamicFilter: DynamicFilterBase + fmt::Debug + Send + Sync + 'static {
+    /// Whether entities of `archetype` can match this filter.
+    ///
+    /// This is called once per archetype, when the query state is updated.
+    fn matches_archetype(&self, archetype: &Archetype) -> bool {
+        let _ = archetype;
+        true
+    }
+    /// Whether [`Self::matches_entity`] should be called on each entity of the
+    /// matched archetypes.
+    fn has_entity_check(&self) -> bool {
+        false
+    }
+    /// Whether `entity` matches this filter, called when iterating or getting
+    /// entities if [`Self::has_entity_check`] is `true`.
+    fn matches_entity(&self, entity: FilterEntity) -> bool {
+        let _ = entity;
+        true
+    }
+    /// Components [`Self::matches_entity`] reads.
+    ///
+    /// They are added to the query's access, so that the scheduler knows of
+    /// them. [`FilterEntity`] only gives access to those components.
+    fn reads(&self) -> &[ComponentId] {
+        &[]
+    }
+}
+
+/// Object-safe helpers for [`DynamicFilter`], implemented for all
+/// `Clone + PartialEq` types.
+///
+/// Two [`crate::DynamicQuery`] are only equal if their custom filters are of
+/// the same type and equal, so that [`crate::DynamicQueryCache`] shares their
+/// states. `PartialEq` must be reflexive, filters not equal to themselves
+/// would add a new state to the cache each time they are looked up.
+pub trait DynamicFilterBase {
+    fn clone_box(&self) -> Box<dyn DynamicFilter>;
+    fn as_any(&self) -> &dyn Any;
+    /// Whether `self` and `other` are of the same type and equal.
+    fn dyn_eq(&self, other: &dyn DynamicFilter) -> bool;
+}
+impl<T: DynamicFilter + Clone + PartialEq> DynamicFilterBase for T {
+    fn clone_box(&self) -> Box<dyn DynamicFilter> {
+        Box::new(self.clone())
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn dyn_eq(&self, other: &dyn DynamicFilter) -> bool {
+        other.as_any().downcast_ref::<T>() == Some(self)
+    }
+}
+impl Clone for Box<dyn DynamicFilter> {
+    fn clone(&self) -> Self {
+        self.as_ref().clone_box()
+    }
+}
+impl PartialEq for Box<dyn DynamicFilter> {
+    fn eq(&self, other: &Self) -> bool {
+        self.dyn_eq(other.as_ref())
+    }
+}
+impl Eq for Box<dyn DynamicFilter> {}
+impl dyn DynamicFilter {
+    pub(crate) fn filter_type_id(&self) -> TypeId {
+        self.as_any().type_id()
+    }
+}
+
+/// An entity checked by [`DynamicFilter::matches_entity`].
+///
+/// Only the components in [`DynamicFilter::reads`] can be accessed.
+#[derive(Clone, Copy)]
+pub struct FilterEntity<'a> {
+    cell: UnsafeEntityCell<'a>,
+    reads: &'a [ComponentId],
+}
+impl<'a> FilterEntity<'a> {
+    /// # Safety
+    /// `cell` must allow reading all components in `reads`.
+    pub(crate) unsafe fn new(cell: UnsafeEntityCell<'a>, reads: &'a [ComponentId]) -> Self {
+        FilterEntity { cell, reads }
+    }
+    pub fn id(&self) -> Entity {
+        self.cell.id()
+    }
+    pub fn archetype(&self) -> &'a Archetype {
+        self.cell.archetype()
+    }
+    /// The component `id`, `None` if the entity doesn't have it or it is not
+    /// in [`DynamicFilter::reads`].
+    pub fn get_by_id(&self, id: ComponentId) -> Option<Ptr<'a>> {
+        if !self.reads.contains(&id) {
+            return None;
+        }
+        // SAFETY: `cell` allows reading the `reads` components.
+        unsafe { self.cell.get_by_id(id) }
+    }
+    /// The component `C`, `None` if the entity doesn't have it or it is not
+    /// in [`DynamicFilter::reads`].
+    pub fn get<C: Component>(&self) -> Option<&'a C> {
+        let id = self.cell.world().components().component_id::<C>()?;
+        // SAFETY: `id` is the `ComponentId` of `C`.
+        self.get_by_id(id).map(|ptr| unsafe { ptr.deref() })
+    }
+}
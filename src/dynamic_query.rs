@@ -1,18 +1,110 @@
+use bevy_ecs::component::{ComponentId, Components};
 use bevy_ecs::prelude::{Entity, World};
-use bevy_reflect::Reflect;
+use bevy_reflect::{Reflect, TypeInfo};
+use thiserror::Error;
 
-use crate::{fetches::Fetches, filters::Filters, DQuery, DynamicState, Fetch, OrFilters};
+use crate::pretty_print::DisplayQuery;
+use crate::{
+    fetches::Fetches, filters::Filters, DQuery, DynamicState, Fetch, FetchKind, OrFilters,
+};
 
+/// A single fetched item of a [`DynamicRow`](crate::DynamicRow).
+///
+/// Component items hold the [`ComponentId`] of the fetched component, see
+/// [`DynamicItem::component_id`].
 #[derive(Debug)]
 pub enum DynamicItem<'a> {
     Entity(Entity),
-    Read(&'a dyn Reflect),
-    Mut(&'a mut dyn Reflect),
-    OptionRead(Option<&'a dyn Reflect>),
-    OptionMut(Option<&'a mut dyn Reflect>),
+    Read(ComponentId, &'a dyn Reflect),
+    Mut(ComponentId, &'a mut dyn Reflect),
+    OptionRead(ComponentId, Option<&'a dyn Reflect>),
+    OptionMut(ComponentId, Option<&'a mut dyn Reflect>),
+}
+impl<'a> DynamicItem<'a> {
+    pub const fn kind(&self) -> FetchKind {
+        match self {
+            DynamicItem::Entity(_) => FetchKind::Entity,
+            DynamicItem::Read(..) => FetchKind::Read,
+            DynamicItem::Mut(..) => FetchKind::Mut,
+            DynamicItem::OptionRead(..) => FetchKind::OptionRead,
+            DynamicItem::OptionMut(..) => FetchKind::OptionMut,
+        }
+    }
+    /// Whether this item was fetched mutably, even if the component is absent.
+    pub const fn is_mutable(&self) -> bool {
+        matches!(self, DynamicItem::Mut(..) | DynamicItem::OptionMut(..))
+    }
+    /// `false` if this is an optional item and the entity doesn't have the
+    /// component.
+    pub const fn is_present(&self) -> bool {
+        !matches!(
+            self,
+            DynamicItem::OptionRead(_, None) | DynamicItem::OptionMut(_, None)
+        )
+    }
+    /// The fetched component, `None` if this is the entity.
+    ///
+    /// This is the component's id even if an optional component is absent.
+    pub const fn component_id(&self) -> Option<ComponentId> {
+        match self {
+            DynamicItem::Entity(_) => None,
+            DynamicItem::Read(id, _) | DynamicItem::Mut(id, _) => Some(*id),
+            DynamicItem::OptionRead(id, _) | DynamicItem::OptionMut(id, _) => Some(*id),
+        }
+    }
+    pub const fn entity(&self) -> Option<Entity> {
+        match self {
+            DynamicItem::Entity(entity) => Some(*entity),
+            _ => None,
+        }
+    }
+    /// The component value, `None` if this is the entity or the component is
+    /// absent.
+    pub fn as_reflect(&self) -> Option<&dyn Reflect> {
+        match self {
+            DynamicItem::Read(_, value) | DynamicItem::OptionRead(_, Some(value)) => Some(*value),
+            DynamicItem::Mut(_, value) | DynamicItem::OptionMut(_, Some(value)) => Some(*value),
+            _ => None,
+        }
+    }
+    /// Same as [`Self::as_reflect`], but `None` if the item is read-only.
+    pub fn as_reflect_mut(&mut self) -> Option<&mut dyn Reflect> {
+        match self {
+            DynamicItem::Mut(_, value) | DynamicItem::OptionMut(_, Some(value)) => Some(*value),
+            _ => None,
+        }
+    }
+    /// The [`TypeInfo`] of the component, `None` if this is the entity or the
+    /// component is absent.
+    pub fn type_info(&self) -> Option<&'static TypeInfo> {
+        self.as_reflect()?.get_represented_type_info()
+    }
+    pub fn downcast_ref<T: Reflect>(&self) -> Option<&T> {
+        self.as_reflect()?.downcast_ref()
+    }
+    /// `None` if the item is read-only or not a `T`.
+    pub fn downcast_mut<T: Reflect>(&mut self) -> Option<&mut T> {
+        self.as_reflect_mut()?.downcast_mut()
+    }
+}
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum RebindError {
+    #[error("No component with id {0:?} exists in the source world.")]
+    NotInSource(ComponentId),
+    #[error("The component '{0}' is not a rust type, it can't be matched between worlds.")]
+    NoTypeId(String),
+    #[error("The component '{0}' doesn't exist in the target world.")]
+    NotInTarget(String),
 }
 
-#[derive(Clone, Debug)]
+/// A query over reflected components.
+///
+/// `DynamicQuery` is always in canonical form: fetches are sorted by kind and
+/// component id, and filters are sorted and deduplicated. So two queries
+/// with the same fetches and filters declared in a different order are equal,
+/// and have the same hash.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct DynamicQuery {
     pub(crate) fetches: Fetches,
     pub(crate) filters: Filters,
@@ -51,4 +143,44 @@ impl DynamicQuery {
     pub fn from_query<Q: DQuery>(world: &mut World) -> Self {
         Q::dynamic(world)
     }
+    /// Create the same query as this one, but for the `to` world.
+    ///
+    /// `self` must have been created for the `from` world. Components are
+    /// matched between the two worlds by their `TypeId`.
+    ///
+    /// Note that items are ordered by `ComponentId`, so the rebound query may
+    /// return its items in a different order.
+    pub fn rebind(&self, from: &World, to: &World) -> Result<Self, RebindError> {
+        let map = |id| {
+            let info = from.components().get_info(id);
+            let info = info.ok_or(RebindError::NotInSource(id))?;
+            let name = || info.name().to_owned();
+            let type_id = info
+                .type_id()
+                .ok_or_else(|| RebindError::NoTypeId(name()))?;
+            let to_id = to.components().get_id(type_id);
+            to_id.ok_or_else(|| RebindError::NotInTarget(name()))
+        };
+        let fetches = self.fetches.try_map_ids(map)?;
+        let filters = self.filters.try_map_ids(map)?;
+        // unwrap: ids are mapped one-to-one, so if `self` is valid, so is this.
+        Ok(DynamicQuery::new(fetches, filters).unwrap())
+    }
+    /// Display this query with component names rather than ids.
+    ///
+    /// By default, this looks like a rust `Query` type, such as
+    /// `Query<(&mut Health, Option<&Armor>), Or<((With<Player>, Without<Invincible>), With<Enemy>)>>`.
+    /// Use [`DisplayQuery::compact`] for the compact console syntax, such as
+    /// `mut Health ?Armor, Player !Invincible | Enemy`.
+    ///
+    /// Fetches and filters are displayed in the order they are stored in,
+    /// which may differ from the order they were declared in.
+    ///
+    /// With the `parse` feature, the output parses back into this query,
+    /// unless it has [`DynamicFilter`](crate::DynamicFilter)s or predicates
+    /// created with [`Predicate::new`](crate::Predicate::new), which have no
+    /// literal form.
+    pub fn display<'a>(&'a self, components: &'a Components) -> DisplayQuery<'a> {
+        DisplayQuery::new(self, components)
+    }
 }
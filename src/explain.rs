@@ -0,0 +1,219 @@
+//! Explain why a [`DynamicState`] matches or rejects archetypes.
+//!
+//! See [`DynamicState::explain`].
+use std::fmt;
+
+use bevy_ecs::archetype::{Archetype, ArchetypeId};
+use bevy_ecs::component::{ComponentId, Components};
+use bevy_ecs::world::World;
+use bevy_utils::get_short_name;
+
+use crate::builder::Fetch;
+use crate::filters::{Conjunction, FilterKind};
+use crate::DynamicState;
+
+/// A component, with its name as registered in the world.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NamedComponent {
+    pub id: ComponentId,
+    pub name: String,
+}
+impl NamedComponent {
+    fn new(id: ComponentId, components: &Components) -> Self {
+        let name = components
+            .get_info(id)
+            .map_or("<unregistered>", |info| info.name());
+        NamedComponent { id, name: name.to_owned() }
+    }
+}
+impl fmt::Display for NamedComponent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&get_short_name(&self.name))
+    }
+}
+
+/// Why a conjunction (an `Or` clause) rejected an archetype.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Rejection {
+    /// The archetype doesn't have a component in a `With` filter.
+    MissingWith(NamedComponent),
+    /// The archetype doesn't have a component in a `Changed` filter.
+    MissingChanged(NamedComponent),
+    /// The archetype doesn't have a component in an `Added` filter.
+    MissingAdded(NamedComponent),
+    /// The archetype has a component in a `Without` filter.
+    HasWithout(NamedComponent),
+    /// The archetype doesn't have the component of a [`Predicate`](crate::Predicate).
+    MissingPredicate(NamedComponent),
+    /// A [`DynamicFilter`](crate::DynamicFilter) rejected the archetype,
+    /// with the filter's `Debug` representation.
+    Custom(String),
+}
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::MissingWith(c) => write!(f, "missing With<{c}>"),
+            Rejection::MissingChanged(c) => write!(f, "missing Changed<{c}>"),
+            Rejection::MissingAdded(c) => write!(f, "missing Added<{c}>"),
+            Rejection::HasWithout(c) => write!(f, "has Without<{c}>"),
+            Rejection::MissingPredicate(c) => write!(f, "missing {c} for predicate"),
+            Rejection::Custom(filter) => write!(f, "rejected by {filter}"),
+        }
+    }
+}
+
+/// How a single conjunction of the query's filters handles an archetype.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConjunctionExplanation {
+    /// The filters of this conjunction the archetype doesn't satisfy.
+    ///
+    /// The conjunction matches the archetype if this is empty.
+    pub rejections: Vec<Rejection>,
+    /// Whether this conjunction has `Changed`, `Added` or predicate filters,
+    /// meaning entities of matched archetypes are further filtered individually.
+    pub tick_filtered: bool,
+}
+impl ConjunctionExplanation {
+    fn new(conjunction: Conjunction, archetype: &Archetype, components: &Components) -> Self {
+        let rejections = conjunction.filters.iter().filter_map(|filter| {
+            let id = filter.id();
+            let named = || NamedComponent::new(id, components);
+            match filter.kind() {
+                FilterKind::Without if archetype.contains(id) => {
+                    Some(Rejection::HasWithout(named()))
+                }
+                FilterKind::Without => None,
+                _ if archetype.contains(id) => None,
+                FilterKind::With => Some(Rejection::MissingWith(named())),
+                FilterKind::Changed => Some(Rejection::MissingChanged(named())),
+                FilterKind::Added => Some(Rejection::MissingAdded(named())),
+            }
+        });
+        let predicates = conjunction.predicates.iter().map(|p| p.id());
+        let predicates = predicates.filter(|id| !archetype.contains(*id));
+        let predicates =
+            predicates.map(|id| Rejection::MissingPredicate(NamedComponent::new(id, components)));
+        let customs = conjunction.customs.iter();
+        let customs = customs.filter(|c| !c.matches_archetype(archetype));
+        let customs = customs.map(|c| Rejection::Custom(format!("{c:?}")));
+        ConjunctionExplanation {
+            rejections: rejections.chain(predicates).chain(customs).collect(),
+            tick_filtered: conjunction.has_entity_filter(),
+        }
+    }
+    pub fn matched(&self) -> bool {
+        self.rejections.is_empty()
+    }
+}
+
+/// How a [`DynamicState`] handles a single archetype.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArchetypeExplanation {
+    pub id: ArchetypeId,
+    /// How many entities are in this archetype.
+    pub entity_count: usize,
+    /// Non-optional fetched components this archetype doesn't have.
+    ///
+    /// The archetype is rejected if this is not empty, whatever the filters.
+    pub missing_fetches: Vec<NamedComponent>,
+    /// One explanation per conjunction (`Or` clause) in the query filters.
+    ///
+    /// Empty if the query has no filters.
+    pub conjunctions: Vec<ConjunctionExplanation>,
+}
+impl ArchetypeExplanation {
+    /// Whether the query matches this archetype.
+    pub fn matched(&self) -> bool {
+        let conjunction_matches = self.conjunctions.iter().any(|c| c.matched());
+        self.missing_fetches.is_empty() && (self.conjunctions.is_empty() || conjunction_matches)
+    }
+    /// Whether entities of this archetype are filtered by their change ticks
+    /// or predicates, on top of the archetype match.
+    pub fn tick_filtered(&self) -> bool {
+        let ticked = |c: &ConjunctionExplanation| c.matched() && c.tick_filtered;
+        self.matched() && self.conjunctions.iter().any(ticked)
+    }
+}
+impl fmt::Display for ArchetypeExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { id, entity_count, .. } = self;
+        write!(f, "{id:?} ({entity_count} entities): ")?;
+        match (self.matched(), self.tick_filtered()) {
+            (true, true) => writeln!(f, "matched, filtered per entity")?,
+            (true, false) => writeln!(f, "matched")?,
+            (false, _) => writeln!(f, "rejected")?,
+        }
+        for missing in &self.missing_fetches {
+            writeln!(f, "  missing fetched {missing}")?;
+        }
+        for (i, conjunction) in self.conjunctions.iter().enumerate() {
+            write!(f, "  conjunction {i}: ")?;
+            if conjunction.matched() {
+                f.write_str("matched")?;
+            } else {
+                f.write_str("rejected: ")?;
+                for (j, rejection) in conjunction.rejections.iter().enumerate() {
+                    let sep = if j == 0 { "" } else { ", " };
+                    write!(f, "{sep}{rejection}")?;
+                }
+            }
+            if conjunction.tick_filtered {
+                f.write_str(" (has per-entity filters)")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// A report of how a [`DynamicState`] matches each archetype of a world.
+///
+/// Use the `Display` implementation for a human-readable report, or inspect
+/// [`Explanation::archetypes`] directly.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Explanation {
+    pub archetypes: Vec<ArchetypeExplanation>,
+}
+impl Explanation {
+    /// The archetypes the query matches.
+    pub fn matched(&self) -> impl Iterator<Item = &ArchetypeExplanation> {
+        self.archetypes.iter().filter(|a| a.matched())
+    }
+}
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.archetypes.len();
+        let matched = self.matched().count();
+        writeln!(f, "{matched} of {total} archetypes matched")?;
+        self.archetypes.iter().try_for_each(|a| a.fmt(f))
+    }
+}
+
+impl DynamicState {
+    /// Explain, for each archetype in `world`, whether this state matches it,
+    /// and if not, which fetch or filter rejected it.
+    ///
+    /// This doesn't update the state, and re-checks every archetype,
+    /// it is meant for debugging.
+    pub fn explain(&self, world: &World) -> Explanation {
+        let components = world.components();
+        let fetch_ids = self.fetches.ids(Fetch::READ_IDX);
+        let fetch_ids: Vec<_> = fetch_ids.chain(self.fetches.ids(Fetch::MUT_IDX)).collect();
+
+        let explain_archetype = |archetype: &Archetype| {
+            let missing = fetch_ids.iter().filter(|id| !archetype.contains(**id));
+            let missing = missing.map(|id| NamedComponent::new(*id, components));
+            let conjunctions = self.filters.conjunctions();
+            let conjunctions =
+                conjunctions.map(|c| ConjunctionExplanation::new(c, archetype, components));
+            ArchetypeExplanation {
+                id: archetype.id(),
+                entity_count: archetype.len(),
+                missing_fetches: missing.collect(),
+                conjunctions: conjunctions.collect(),
+            }
+        };
+        let archetypes = world.archetypes().iter().map(explain_archetype);
+        Explanation { archetypes: archetypes.collect() }
+    }
+}
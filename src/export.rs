@@ -0,0 +1,247 @@
+//! Serialize a [`Snapshot`] of query results, and deserialize it back for
+//! [`DynamicState::apply_snapshot`].
+//!
+//! Each row is the entity, as [`Entity::to_bits`], and a map from the full
+//! type name of its components to their reflected value:
+//!
+//! ```text
+//! [(entity: 4294967296, components: {"my_game::Health": (current: 3)})]
+//! ```
+//!
+//! [`SnapshotSerializer`] and [`SnapshotDeserializer`] work with any serde
+//! format. The `ron` and `json` features add [`Snapshot`] methods to read
+//! and write RON and JSON text.
+//!
+//! [`DynamicState::apply_snapshot`]: crate::DynamicState::apply_snapshot
+use std::fmt;
+
+use bevy_ecs::component::Components;
+use bevy_ecs::prelude::Entity;
+use bevy_reflect::serde::{
+    TypeRegistrationDeserializer, TypedReflectDeserializer, TypedReflectSerializer,
+};
+use bevy_reflect::{ReflectFromReflect, TypeRegistry};
+use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, SerializeMap, SerializeSeq, SerializeStruct};
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::{Snapshot, SnapshotRow, SnapshotValue};
+
+const ROW_FIELDS: &[&str] = &["entity", "components"];
+
+/// Serialize a [`Snapshot`], using `registry` to serialize component values.
+pub struct SnapshotSerializer<'a> {
+    pub snapshot: &'a Snapshot,
+    pub registry: &'a TypeRegistry,
+}
+impl<'a> SnapshotSerializer<'a> {
+    pub fn new(snapshot: &'a Snapshot, registry: &'a TypeRegistry) -> Self {
+        SnapshotSerializer { snapshot, registry }
+    }
+}
+impl Serialize for SnapshotSerializer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let rows = &self.snapshot.rows;
+        let mut seq = serializer.serialize_seq(Some(rows.len()))?;
+        for row in rows {
+            seq.serialize_element(&RowSerializer { row, registry: self.registry })?;
+        }
+        seq.end()
+    }
+}
+
+struct RowSerializer<'a> {
+    row: &'a SnapshotRow,
+    registry: &'a TypeRegistry,
+}
+impl Serialize for RowSerializer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let components = ValuesSerializer { values: &self.row.values, registry: self.registry };
+        let mut row = serializer.serialize_struct("SnapshotRow", ROW_FIELDS.len())?;
+        row.serialize_field("entity", &self.row.entity.to_bits())?;
+        row.serialize_field("components", &components)?;
+        row.end()
+    }
+}
+
+struct ValuesSerializer<'a> {
+    values: &'a [SnapshotValue],
+    registry: &'a TypeRegistry,
+}
+impl Serialize for ValuesSerializer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.values.len()))?;
+        for SnapshotValue { value, .. } in self.values {
+            let Some(info) = value.get_represented_type_info() else {
+                let msg = format!("`{}` doesn't represent a known type", value.type_name());
+                return Err(ser::Error::custom(msg));
+            };
+            let value = TypedReflectSerializer::new(value.as_ref(), self.registry);
+            map.serialize_entry(info.type_name(), &value)?;
+        }
+        map.end()
+    }
+}
+
+/// Deserialize a [`Snapshot`] serialized with [`SnapshotSerializer`].
+///
+/// Component names are resolved with `registry` and `components`, they must
+/// be reflect-registered components of the world the snapshot is applied to.
+pub struct SnapshotDeserializer<'a> {
+    pub registry: &'a TypeRegistry,
+    pub components: &'a Components,
+}
+impl<'a> SnapshotDeserializer<'a> {
+    pub fn new(registry: &'a TypeRegistry, components: &'a Components) -> Self {
+        SnapshotDeserializer { registry, components }
+    }
+}
+impl<'de> DeserializeSeed<'de> for SnapshotDeserializer<'_> {
+    type Value = Snapshot;
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<Snapshot, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+impl<'de> Visitor<'de> for SnapshotDeserializer<'_> {
+    type Value = Snapshot;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of snapshot rows")
+    }
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Snapshot, A::Error> {
+        let mut rows = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(row) = seq.next_element_seed(RowDeserializer(&self))? {
+            rows.push(row);
+        }
+        Ok(Snapshot { rows })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum RowField {
+    Entity,
+    Components,
+}
+
+struct RowDeserializer<'a, 'r>(&'r SnapshotDeserializer<'a>);
+impl<'de> DeserializeSeed<'de> for RowDeserializer<'_, '_> {
+    type Value = SnapshotRow;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_struct("SnapshotRow", ROW_FIELDS, self)
+    }
+}
+impl<'de> Visitor<'de> for RowDeserializer<'_, '_> {
+    type Value = SnapshotRow;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a snapshot row")
+    }
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let (mut entity, mut values) = (None, None);
+        while let Some(field) = map.next_key()? {
+            match field {
+                RowField::Entity if entity.is_some() => {
+                    return Err(de::Error::duplicate_field("entity"));
+                }
+                RowField::Components if values.is_some() => {
+                    return Err(de::Error::duplicate_field("components"));
+                }
+                RowField::Entity => entity = Some(Entity::from_bits(map.next_value()?)),
+                RowField::Components => {
+                    values = Some(map.next_value_seed(ValuesDeserializer(self.0))?)
+                }
+            }
+        }
+        let entity = entity.ok_or_else(|| de::Error::missing_field("entity"))?;
+        let values = values.ok_or_else(|| de::Error::missing_field("components"))?;
+        Ok(SnapshotRow { entity, values })
+    }
+}
+
+struct ValuesDeserializer<'a, 'r>(&'r SnapshotDeserializer<'a>);
+impl<'de> DeserializeSeed<'de> for ValuesDeserializer<'_, '_> {
+    type Value = Vec<SnapshotValue>;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+impl<'de> Visitor<'de> for ValuesDeserializer<'_, '_> {
+    type Value = Vec<SnapshotValue>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map from component type names to their value")
+    }
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let SnapshotDeserializer { registry, components } = *self.0;
+        let mut values = Vec::with_capacity(map.size_hint().unwrap_or(0));
+        let names = || TypeRegistrationDeserializer::new(registry);
+        while let Some(registration) = map.next_key_seed(names())? {
+            let name = registration.type_name();
+            let Some(id) = components.get_id(registration.type_id()) else {
+                let msg = format!("`{name}` is not a component of this world");
+                return Err(de::Error::custom(msg));
+            };
+            let value =
+                map.next_value_seed(TypedReflectDeserializer::new(registration, registry))?;
+            // Prefer the concrete type, so that values can be downcast.
+            let from_reflect = registration.data::<ReflectFromReflect>();
+            let value = from_reflect
+                .and_then(|f| f.from_reflect(value.as_ref()))
+                .unwrap_or(value);
+            values.push(SnapshotValue { id, value });
+        }
+        Ok(values)
+    }
+}
+
+#[cfg(feature = "ron")]
+impl Snapshot {
+    /// This snapshot as pretty-printed RON, see [`SnapshotSerializer`].
+    pub fn to_ron(&self, registry: &TypeRegistry) -> Result<String, ron::Error> {
+        let serializer = SnapshotSerializer::new(self, registry);
+        ron::ser::to_string_pretty(&serializer, ron::ser::PrettyConfig::default())
+    }
+    /// Read a snapshot written by [`Self::to_ron`], see [`SnapshotDeserializer`].
+    pub fn from_ron(
+        text: &str,
+        registry: &TypeRegistry,
+        components: &Components,
+    ) -> Result<Self, ron::error::SpannedError> {
+        let mut deserializer = ron::Deserializer::from_str(text)?;
+        let snapshot = SnapshotDeserializer::new(registry, components);
+        let snapshot = snapshot.deserialize(&mut deserializer);
+        let snapshot = snapshot.map_err(|e| deserializer.span_error(e))?;
+        deserializer.end().map_err(|e| deserializer.span_error(e))?;
+        Ok(snapshot)
+    }
+}
+
+#[cfg(feature = "json")]
+impl Snapshot {
+    /// This snapshot as pretty-printed JSON, see [`SnapshotSerializer`].
+    pub fn to_json(&self, registry: &TypeRegistry) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(&SnapshotSerializer::new(self, registry))
+    }
+    /// Read a snapshot written by [`Self::to_json`], see [`SnapshotDeserializer`].
+    pub fn from_json(
+        text: &str,
+        registry: &TypeRegistry,
+        components: &Components,
+    ) -> Result<Self, serde_json::Error> {
+        let mut deserializer = serde_json::Deserializer::from_str(text);
+        let snapshot = SnapshotDeserializer::new(registry, components);
+        let snapshot = snapshot.deserialize(&mut deserializer)?;
+        deserializer.end()?;
+        Ok(snapshot)
+    }
+}
@@ -1,3 +1,4 @@
+use std::hash::{Hash, Hasher};
 use std::{collections::HashSet, fmt};
 
 use bevy_ecs::{component::ComponentId, world::unsafe_world_cell::UnsafeEntityCell};
@@ -12,8 +13,8 @@ use crate::dynamic_query::DynamicItem;
 
 #[derive(Clone)]
 pub struct FetchComponent {
-    id: ComponentId,
-    from_ptr: ReflectFromPtr,
+    pub(crate) id: ComponentId,
+    pub(crate) from_ptr: ReflectFromPtr,
 }
 impl fmt::Debug for FetchComponent {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -21,11 +22,31 @@ impl fmt::Debug for FetchComponent {
     }
 }
 
+/// `Fetches` are compared by component id, since the `ReflectFromPtr` of a
+/// component is always the same.
 #[derive(Clone, Debug)]
 pub struct Fetches {
     pub(crate) has_entity: bool,
     // TODO(perf): do not store the TypeId, which is 128 bits
-    pub(crate) components: JaggedArray<FetchComponent, u8, [u8; 3]>,
+    pub(crate) components: JaggedArray<FetchComponent, u32, [u32; 3]>,
+}
+impl PartialEq for Fetches {
+    fn eq(&self, other: &Self) -> bool {
+        let same_kind = |idx| self.ids(idx).eq(other.ids(idx));
+        self.has_entity == other.has_entity
+            && (Fetch::READ_IDX..=Fetch::OPTION_MUT_IDX).all(same_kind)
+    }
+}
+impl Eq for Fetches {}
+impl Hash for Fetches {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.has_entity.hash(state);
+        for kind_idx in Fetch::READ_IDX..=Fetch::OPTION_MUT_IDX {
+            let ids = self.components.get_row(kind_idx).unwrap_or_default();
+            ids.len().hash(state);
+            ids.iter().for_each(|comp| comp.id.hash(state));
+        }
+    }
 }
 impl Fetches {
     pub fn new(mut fetches: Vec<Fetch>) -> Option<Self> {
@@ -40,8 +61,8 @@ impl Fetches {
         let mut last_idx = 0;
         let data = fetches.into_iter().enumerate().map(|(i, fetch)| {
             let index = fetch.discriminant_index();
-            for to_catchup in last_idx..index {
-                ends[to_catchup] = i as u8;
+            for (to_catchup, end) in ends.iter_mut().enumerate().take(index).skip(last_idx) {
+                *end = i as u32;
                 trace!("^^^ Fetch row {to_catchup} ^^^");
             }
             last_idx = index;
@@ -54,9 +75,9 @@ impl Fetches {
         if duplicates_in(&data) {
             return None;
         }
-        for i in last_idx..3 {
+        for (i, end) in ends.iter_mut().enumerate().skip(last_idx) {
             trace!("^^ Fetch row {i} ^^");
-            ends[i] = data.len() as u8;
+            *end = data.len() as u32;
         }
         let components = JaggedArray::new(ends, data).unwrap();
         Some(Fetches { has_entity, components })
@@ -64,6 +85,55 @@ impl Fetches {
     pub fn len(&self) -> usize {
         self.components.len() + (self.has_entity as u8 as usize)
     }
+    /// The [`ComponentId`]s of fetches of kind `kind_idx`, one of the `Fetch::*_IDX`.
+    pub(crate) fn ids(&self, kind_idx: usize) -> impl Iterator<Item = ComponentId> + '_ {
+        let row = self.components.get_row(kind_idx).unwrap_or_default();
+        row.iter().map(|comp| comp.id)
+    }
+    /// The [`Fetch`]es of this `Fetches`, with their component ids mapped by `map`.
+    pub(crate) fn try_map_ids<E>(
+        &self,
+        mut map: impl FnMut(ComponentId) -> Result<ComponentId, E>,
+    ) -> Result<Vec<Fetch>, E> {
+        let mut fetches = Vec::with_capacity(self.len());
+        for kind_idx in Fetch::READ_IDX..=Fetch::OPTION_MUT_IDX {
+            for comp in self.components.get_row(kind_idx).unwrap_or_default() {
+                let from_ptr = comp.from_ptr.clone();
+                let data = FetchData { id: map(comp.id)?, from_ptr };
+                fetches.push(match kind_idx {
+                    Fetch::READ_IDX => Fetch::Read(data),
+                    Fetch::MUT_IDX => Fetch::Mut(data),
+                    Fetch::OPTION_READ_IDX => Fetch::OptionRead(data),
+                    _ => Fetch::OptionMut(data),
+                });
+            }
+        }
+        if self.has_entity {
+            fetches.push(Fetch::Entity);
+        }
+        Ok(fetches)
+    }
+    /// The [`ReflectFromPtr`] of the fetched component `id`, if it is fetched.
+    pub(crate) fn reflect_from_ptr(&self, id: ComponentId) -> Option<&ReflectFromPtr> {
+        let comps = self
+            .components
+            .rows(Fetch::READ_IDX..=Fetch::OPTION_MUT_IDX);
+        comps
+            .iter()
+            .find(|comp| comp.id == id)
+            .map(|comp| &comp.from_ptr)
+    }
+    /// The fetched components, in the order of their items in a row.
+    pub(crate) fn components(&self) -> &[FetchComponent] {
+        self.components
+            .rows(Fetch::READ_IDX..=Fetch::OPTION_MUT_IDX)
+    }
+    /// The fetched component of the `index`th item of a row, `None` if it is
+    /// the [`DynamicItem::Entity`] item or out of bounds.
+    pub(crate) fn item_component(&self, index: usize) -> Option<&FetchComponent> {
+        let index = index.checked_sub(self.has_entity as usize)?;
+        self.components().get(index)
+    }
     #[inline]
     pub fn all_included(&self, ids: impl Iterator<Item = ComponentId>) -> bool {
         let comps = self.components.rows(Fetch::READ_IDX..=Fetch::MUT_IDX);
@@ -72,15 +142,13 @@ impl Fetches {
 
         // TODO(perf): Likely can avoid O(n²). If only `ComponedId`s were
         // ordered in `Archetype::components()`…
-        let mut max = 0;
         for id in ids {
             if let Some(idx) = comps.iter().position(|x| x.id == id) {
                 trace!("all_included: found {id:?} in fetches");
                 found.enable_bit_extending(idx);
-                max = max.max(idx as usize);
             }
         }
-        found.ones_in_range(..max).all_one()
+        found.ones().count() == comps.len()
     }
 
     /// # Safety
@@ -109,7 +177,7 @@ pub struct RoFetchesIter<'w, 's>(FetchesIter<'w, 's>);
 
 pub struct FetchesIter<'w, 's> {
     has_entity: bool,
-    fetches: &'s JaggedArray<FetchComponent, u8, [u8; 3]>,
+    fetches: &'s JaggedArray<FetchComponent, u32, [u32; 3]>,
     entity: UnsafeEntityCell<'w>,
     row_index: usize,
     current_row: &'s [FetchComponent],
@@ -160,7 +228,7 @@ impl<'w, 's> Iterator for FetchesIter<'w, 's> {
                 let ptr = unsafe { self.entity.get_by_id(comp.id).prod_unchecked_unwrap() };
                 let reflect = unsafe { comp.from_ptr.as_reflect_ptr(ptr) };
 
-                Some(DynamicItem::Read(reflect))
+                Some(DynamicItem::Read(comp.id, reflect))
             }
             Fetch::MUT_IDX => {
                 // SAFETY: Same as above
@@ -168,14 +236,14 @@ impl<'w, 's> Iterator for FetchesIter<'w, 's> {
                 let ptr = unsafe { ptr.assert_unique() };
                 let reflect = unsafe { comp.from_ptr.as_reflect_ptr_mut(ptr) };
 
-                Some(DynamicItem::Mut(reflect))
+                Some(DynamicItem::Mut(comp.id, reflect))
             }
             Fetch::OPTION_READ_IDX => {
                 // SAFETY: Same as point (3) of above
                 let ptr = unsafe { self.entity.get_by_id(comp.id) };
                 let reflect = unsafe { ptr.map(|p| comp.from_ptr.as_reflect_ptr(p)) };
 
-                Some(DynamicItem::OptionRead(reflect))
+                Some(DynamicItem::OptionRead(comp.id, reflect))
             }
             Fetch::OPTION_MUT_IDX => {
                 // SAFETY: Same as point (3) of above
@@ -183,7 +251,7 @@ impl<'w, 's> Iterator for FetchesIter<'w, 's> {
                 let ptr = unsafe { ptr.map(|p| p.assert_unique()) };
                 let reflect = unsafe { ptr.map(|p| comp.from_ptr.as_reflect_ptr_mut(p)) };
 
-                Some(DynamicItem::OptionMut(reflect))
+                Some(DynamicItem::OptionMut(comp.id, reflect))
             }
             // TODO(perf): check this is elided
             _ => unreachable!(),
@@ -201,7 +269,7 @@ impl<'w, 's> Iterator for RoFetchesIter<'w, 's> {
         let Some((comp, remaining)) = self.0.current_row.split_first() else {
             self.0.current_row = self.0.fetches.get_row(self.0.row_index)?;
             self.0.row_index += 1;
-            return self.0.next();
+            return self.next();
         };
         self.0.current_row = remaining;
 
@@ -214,14 +282,14 @@ impl<'w, 's> Iterator for RoFetchesIter<'w, 's> {
                 let ptr = unsafe { self.0.entity.get_by_id(comp.id).prod_unchecked_unwrap() };
                 let reflect = unsafe { comp.from_ptr.as_reflect_ptr(ptr) };
 
-                Some(DynamicItem::Read(reflect))
+                Some(DynamicItem::Read(comp.id, reflect))
             }
             Fetch::OPTION_MUT_IDX | Fetch::OPTION_READ_IDX => {
                 // SAFETY: Same as point (3) of above
                 let ptr = unsafe { self.0.entity.get_by_id(comp.id) };
                 let reflect = unsafe { ptr.map(|p| comp.from_ptr.as_reflect_ptr(p)) };
 
-                Some(DynamicItem::OptionRead(reflect))
+                Some(DynamicItem::OptionRead(comp.id, reflect))
             }
             _ => {
                 // SAFETY: The `fetches` iterator comes from `Fetches.components`,
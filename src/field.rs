@@ -0,0 +1,78 @@
+//! Address a field of a fetched component, as `Component.path.to.field`.
+use bevy_ecs::component::{ComponentId, Components};
+use bevy_ecs::world::unsafe_world_cell::UnsafeEntityCell;
+use bevy_reflect::{ParsedPath, Reflect, ReflectFromPtr};
+use bevy_utils::get_short_name;
+use thiserror::Error;
+
+use crate::builder::Fetch;
+use crate::fetches::Fetches;
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum FieldError {
+    #[error("'{0}' is not a component fetched by this query.")]
+    NotFetched(String),
+    #[error("Several fetched components are named '{0}', use their full type name.")]
+    Ambiguous(String),
+    #[error("Invalid reflect path: {0}")]
+    Path(String),
+}
+
+/// A field within a component fetched by a [`DynamicState`](crate::DynamicState).
+pub(crate) struct FieldPath<'s> {
+    id: ComponentId,
+    from_ptr: &'s ReflectFromPtr,
+    path: ParsedPath,
+}
+impl<'s> FieldPath<'s> {
+    /// Parse `path`, the name of a component in `fetches`, optionally followed
+    /// by a reflect path within it, such as `Health.current`.
+    ///
+    /// The component name is either its full type name or its short name.
+    /// Short names shared by several fetched components are ambiguous.
+    pub(crate) fn new(
+        fetches: &'s Fetches,
+        components: &Components,
+        path: &str,
+    ) -> Result<Self, FieldError> {
+        let split = path.find(['.', '[', '#']).unwrap_or(path.len());
+        let (name, field_path) = path.split_at(split);
+        let field_path = field_path.strip_prefix('.').unwrap_or(field_path);
+
+        let full_name = |id: &ComponentId| components.get_info(*id).map(|info| info.name());
+        let is_named = |id: &ComponentId| full_name(id) == Some(name);
+        let is_short_named =
+            |id: &ComponentId| full_name(id).is_some_and(|full| get_short_name(full) == name);
+        let fetched = || (Fetch::READ_IDX..=Fetch::OPTION_MUT_IDX).flat_map(|i| fetches.ids(i));
+        let not_fetched = || FieldError::NotFetched(name.to_owned());
+        let id = match fetched().find(is_named) {
+            Some(id) => id,
+            None => {
+                let mut short_named = fetched().filter(is_short_named);
+                let id = short_named.next().ok_or_else(not_fetched)?;
+                if short_named.next().is_some() {
+                    return Err(FieldError::Ambiguous(name.to_owned()));
+                }
+                id
+            }
+        };
+        let from_ptr = fetches.reflect_from_ptr(id).ok_or_else(not_fetched)?;
+        let path = ParsedPath::parse(field_path);
+        let path = path.map_err(|e| FieldError::Path(e.to_string()))?;
+        Ok(FieldPath { id, from_ptr, path })
+    }
+    /// The component this field is in.
+    pub(crate) fn id(&self) -> ComponentId {
+        self.id
+    }
+    /// The value of this field in `entity`, `None` if `entity` doesn't have it.
+    ///
+    /// # Safety
+    /// `entity` must allow reading the component of this field.
+    pub(crate) unsafe fn get<'w>(&self, entity: UnsafeEntityCell<'w>) -> Option<&'w dyn Reflect> {
+        let component = entity.get_by_id(self.id)?;
+        // SAFETY: `from_ptr` is the `ReflectFromPtr` of the component `id`.
+        let component = self.from_ptr.as_reflect_ptr(component);
+        self.path.reflect_element(component).ok()
+    }
+}
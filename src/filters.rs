@@ -1,4 +1,5 @@
 use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
 
 use bevy_ecs::archetype::Archetype;
 use bevy_ecs::component::{ComponentId, Tick};
@@ -9,21 +10,30 @@ use tracing::trace;
 
 use crate::builder::{AndFilter, AndFilters, OrFilters};
 use crate::debug_unchecked::DebugUnchecked;
+use crate::dynamic_filter::{DynamicFilter, FilterEntity};
 use crate::fetches::Fetches;
+use crate::predicate::Predicate;
 use crate::state::Ticks;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct Filters(JaggedArray<Filter>);
+pub struct Filters {
+    filters: JaggedArray<Filter>,
+    /// The [`Predicate`]s of each conjunction, in the same order as `filters` rows.
+    predicates: Box<[Box<[Predicate]>]>,
+    /// The [`DynamicFilter`]s of each conjunction, in the same order as `filters` rows.
+    customs: Box<[Customs]>,
+}
+type Customs = Box<[Box<dyn DynamicFilter>]>;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u32)]
-enum FilterKind {
+pub(crate) enum FilterKind {
     With = 0,
     Changed = 1,
     Added = 2,
     Without = 3,
 }
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Filter {
     /// This is an optimized `enum` where the variant discriminant is stored in
     /// the most significant two bits of `component`.
@@ -42,15 +52,14 @@ impl FilterKind {
     }
 }
 impl Filter {
-    const MASK: u32 = 0x7f_ff_ff_ff;
+    const MASK: u32 = 0x3f_ff_ff_ff;
     const KIND_OFFSET: u32 = 30;
 
     pub const fn id(&self) -> ComponentId {
         let masked = self.component & Self::MASK;
         ComponentId::new(masked as usize)
     }
-    #[allow(unused)]
-    const fn kind(&self) -> FilterKind {
+    pub(crate) const fn kind(&self) -> FilterKind {
         let unmasked = self.component >> Self::KIND_OFFSET;
         FilterKind::from_u32(unmasked)
     }
@@ -61,27 +70,26 @@ impl Filter {
         Filter { component }
     }
 }
-impl From<AndFilter> for Filter {
-    fn from(value: AndFilter) -> Self {
-        match value {
-            AndFilter::With(id) => Self::new(FilterKind::With, id),
-            AndFilter::Without(id) => Self::new(FilterKind::Without, id),
-            AndFilter::Changed(id) => Self::new(FilterKind::Changed, id),
-            AndFilter::Added(id) => Self::new(FilterKind::Added, id),
-        }
-    }
-}
 
 /// [`Filters`] are a list of "conjunction".
 pub struct Conjunction<'a> {
-    filters: &'a [Filter],
+    pub(crate) filters: &'a [Filter],
+    pub(crate) predicates: &'a [Predicate],
+    pub(crate) customs: &'a [Box<dyn DynamicFilter>],
 }
 #[derive(Clone)]
-pub struct Conjunctions<'a>(JaggedArrayRows<'a, Filter>);
+pub struct Conjunctions<'a>(
+    JaggedArrayRows<'a, Filter>,
+    std::slice::Iter<'a, Box<[Predicate]>>,
+    std::slice::Iter<'a, Customs>,
+);
 impl<'a> Iterator for Conjunctions<'a> {
     type Item = Conjunction<'a>;
     fn next(&mut self) -> Option<Self::Item> {
-        self.0.next().map(|filters| Conjunction { filters })
+        let filters = self.0.next()?;
+        let predicates = self.1.next().map_or(&[][..], |p| p);
+        let customs = self.2.next().map_or(&[][..], |c| c);
+        Some(Conjunction { filters, predicates, customs })
     }
 }
 
@@ -91,23 +99,8 @@ struct ChangedFilter<'a>(&'a [Filter]);
 struct AddedFilter<'a>(&'a [Filter]);
 
 impl Filters {
-    pub fn len(&self) -> usize {
-        self.0.height()
-    }
     pub fn is_empty(&self) -> bool {
-        self.0.is_empty()
-    }
-    /// # Safety
-    /// - Filters within conjunctions must be sorted
-    /// - There is no duplicate inclusive filters within each single
-    ///   conjunction.
-    pub unsafe fn new_unchecked(OrFilters(dsl_value): OrFilters) -> Self {
-        let cell_count = dsl_value.iter().map(|x| x.0.len()).sum();
-        let mut builder = jagged_array::Builder::new_with_capacity(dsl_value.len(), cell_count);
-        for AndFilters(filters) in dsl_value.into_iter() {
-            builder.add_row(filters.into_iter().map(Filter::from));
-        }
-        Filters(builder.build())
+        self.filters.is_empty() && self.predicates.is_empty() && self.customs.is_empty()
     }
     pub fn new(OrFilters(dsl_value): OrFilters) -> Option<Self> {
         let cell_count = dsl_value.iter().map(|x| x.0.len()).sum();
@@ -116,18 +109,110 @@ impl Filters {
             "new Filters with {} conjunction of total of {cell_count} terms",
             dsl_value.len()
         );
-        for AndFilters(filters) in dsl_value.into_iter() {
-            let mut filters: Vec<_> = filters.into_iter().map(Filter::from).collect();
+        let mut conjunctions = Vec::with_capacity(dsl_value.len());
+        for AndFilters(and_filters) in dsl_value.into_iter() {
+            let mut filters = Vec::with_capacity(and_filters.len());
+            let mut predicates = Vec::new();
+            let mut customs = Vec::new();
+            for filter in and_filters {
+                match filter {
+                    AndFilter::With(id) => filters.push(Filter::new(FilterKind::With, id)),
+                    AndFilter::Without(id) => filters.push(Filter::new(FilterKind::Without, id)),
+                    AndFilter::Changed(id) => filters.push(Filter::new(FilterKind::Changed, id)),
+                    AndFilter::Added(id) => filters.push(Filter::new(FilterKind::Added, id)),
+                    AndFilter::Predicate(predicate) => predicates.push(predicate),
+                    AndFilter::Custom(custom) => customs.push(custom),
+                }
+            }
             filters.sort_unstable();
             if duplicates_in(&filters) {
                 return None;
             }
+            predicates.sort_by(Predicate::canonical_cmp);
+            predicates.dedup();
+            conjunctions.push((filters, predicates, customs));
+        }
+        // Canonical form: two `Filters` with the same conjunctions in different
+        // orders are equal.
+        // `DynamicFilter`s can't be ordered, they keep their relative order.
+        conjunctions.sort_by(|(l_filters, l_preds, _), (r_filters, r_preds, _)| {
+            let by_predicates = || {
+                let pairs = l_preds.iter().zip(r_preds);
+                let mut orderings = pairs.map(|(l, r)| l.canonical_cmp(r));
+                let first_difference = orderings.find(|ordering| ordering.is_ne());
+                first_difference.unwrap_or_else(|| l_preds.len().cmp(&r_preds.len()))
+            };
+            l_filters.cmp(r_filters).then_with(by_predicates)
+        });
+        conjunctions.dedup();
+        let mut predicates: Vec<Box<[_]>> = Vec::with_capacity(conjunctions.len());
+        let mut customs: Vec<Box<[_]>> = Vec::with_capacity(conjunctions.len());
+        for (filters, conjunction_predicates, conjunction_customs) in conjunctions {
             builder.add_row(filters);
+            predicates.push(conjunction_predicates.into());
+            customs.push(conjunction_customs.into());
+        }
+        // Keep `predicates` and `customs` empty when no conjunctions have any.
+        if predicates.iter().all(|p| p.is_empty()) {
+            predicates.clear();
+        }
+        if customs.iter().all(|c| c.is_empty()) {
+            customs.clear();
+        }
+        Some(Filters {
+            filters: builder.build(),
+            predicates: predicates.into(),
+            customs: customs.into(),
+        })
+    }
+    pub fn conjunctions(&self) -> Conjunctions<'_> {
+        Conjunctions(
+            self.filters.rows_iter(),
+            self.predicates.iter(),
+            self.customs.iter(),
+        )
+    }
+    /// The [`OrFilters`] of this `Filters`, with their component ids mapped by `map`.
+    pub(crate) fn try_map_ids<E>(
+        &self,
+        mut map: impl FnMut(ComponentId) -> Result<ComponentId, E>,
+    ) -> Result<OrFilters, E> {
+        let mut conjunctions = Vec::with_capacity(self.filters.height());
+        for conjunction in self.conjunctions() {
+            let filter_count = conjunction.filters.len()
+                + conjunction.predicates.len()
+                + conjunction.customs.len();
+            let mut filters = Vec::with_capacity(filter_count);
+            for filter in conjunction.filters {
+                let id = map(filter.id())?;
+                filters.push(match filter.kind() {
+                    FilterKind::With => AndFilter::With(id),
+                    FilterKind::Without => AndFilter::Without(id),
+                    FilterKind::Changed => AndFilter::Changed(id),
+                    FilterKind::Added => AndFilter::Added(id),
+                });
+            }
+            for predicate in conjunction.predicates {
+                let id = map(predicate.id())?;
+                filters.push(AndFilter::Predicate(predicate.with_id(id)));
+            }
+            let customs = conjunction.customs.iter().cloned();
+            filters.extend(customs.map(AndFilter::Custom));
+            conjunctions.push(AndFilters(filters));
         }
-        Some(Filters(builder.build()))
+        Ok(OrFilters(conjunctions))
     }
-    pub fn conjunctions(&self) -> Conjunctions {
-        Conjunctions(self.0.rows_iter())
+}
+impl Hash for Filters {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.filters.height().hash(state);
+        self.conjunctions().for_each(|c| {
+            c.filters.hash(state);
+            c.predicates.hash(state);
+            c.customs
+                .iter()
+                .for_each(|c| c.filter_type_id().hash(state));
+        });
     }
 }
 impl TryFrom<OrFilters> for Filters {
@@ -140,7 +225,7 @@ fn duplicates_in(filters: &[Filter]) -> bool {
     let mut encountered = HashSet::with_capacity(filters.len());
     filters.iter().any(|f| !encountered.insert(f.id()))
 }
-fn tick_filters(filters: &[Filter]) -> (ChangedFilter, AddedFilter) {
+fn tick_filters(filters: &[Filter]) -> (ChangedFilter<'_>, AddedFilter<'_>) {
     // A Filter value that always fit at the very end of the previous `FilterKind` filters range.
     let mut last_with = Filter::new(FilterKind::Changed, ComponentId::new(0));
     let mut last_changed = Filter::new(FilterKind::Added, ComponentId::new(0));
@@ -161,7 +246,7 @@ fn tick_filters(filters: &[Filter]) -> (ChangedFilter, AddedFilter) {
     let added_filter = AddedFilter(&filters[first_added..first_without]);
     (changed_filter, added_filter)
 }
-fn filters(filters: &[Filter]) -> (InclusiveFilter, ExclusiveFilter) {
+fn filters(filters: &[Filter]) -> (InclusiveFilter<'_>, ExclusiveFilter<'_>) {
     // A Filter value that always fit at the very end of the inclusive filters range.
     let mut last_inclusive = Filter::new(FilterKind::Without, ComponentId::new(0));
     last_inclusive.component -= 1;
@@ -178,6 +263,16 @@ impl Conjunction<'_> {
         let has_tick = |f: &Filter| matches!(f.kind(), Added | Changed);
         self.filters.iter().fold(false, |acc, f| acc | has_tick(f))
     }
+    /// Whether entities of matched archetypes need to be checked individually,
+    /// for tick filters, [`Predicate`]s or [`DynamicFilter`]s.
+    pub(crate) fn has_entity_filter(&self) -> bool {
+        let custom_check = self.customs.iter().any(|c| c.has_entity_check());
+        !self.predicates.is_empty() || custom_check || self.has_tick_filter()
+    }
+    /// Whether `archetype` passes the [`DynamicFilter::matches_archetype`] checks.
+    pub(crate) fn customs_match(&self, archetype: &Archetype) -> bool {
+        self.customs.iter().all(|c| c.matches_archetype(archetype))
+    }
     // O(n²) where n is sizeof archetype
     pub fn includes(&self, fetches: &Fetches, archetype: &Archetype) -> bool {
         // NOTE(perf): We don't skip this on `fetch_archetype == false` because
@@ -186,21 +281,14 @@ impl Conjunction<'_> {
         let include_filter = inclusive.all_included(archetype.components());
         let exclude_filter = exclusive.any_excluded(archetype.components());
         let fetch_archetype = fetches.all_included(archetype.components());
+        let predicates = self.predicates.iter().all(|p| archetype.contains(p.id()));
         trace!("inc:{include_filter}, exc:{exclude_filter}, arch:{fetch_archetype}");
 
-        fetch_archetype && include_filter && !exclude_filter
-    }
-
-    // `O(n²)` where `n` is number of filters
-    pub fn in_filter(&self, archetype: &Archetype) -> bool {
-        // NOTE(perf): We don't skip this on `fetch_archetype == false` because
-        // we hope the optimizer can merge `all_included` `for` with this one.
-        let (inclusive, exclusive) = filters(self.filters);
-        let include_filter = inclusive.all_included(archetype.components());
-        let exclude_filter = exclusive.any_excluded(archetype.components());
-        trace!("inc:{include_filter}, exc:{exclude_filter}");
-
-        include_filter && !exclude_filter
+        fetch_archetype
+            && include_filter
+            && !exclude_filter
+            && predicates
+            && self.customs_match(archetype)
     }
 
     // `O(c)` where `c` number of tick filters.
@@ -212,6 +300,20 @@ impl Conjunction<'_> {
         trace!("Entity {:?} is changed:{changed}, add:{added}", entity.id());
         changed && added
     }
+    /// Whether `entity` passes the tick filters, the [`Predicate`]s and the
+    /// [`DynamicFilter`]s entity checks.
+    ///
+    /// # Safety
+    /// `entity` must allow reading the components of the predicates and
+    /// [`DynamicFilter::reads`].
+    pub unsafe fn within_entity(&self, ticks: Ticks, entity: UnsafeEntityCell) -> bool {
+        let predicates = || self.predicates.iter().all(|p| p.matches_entity(entity));
+        let customs = || {
+            let mut customs = self.customs.iter().filter(|c| c.has_entity_check());
+            customs.all(|c| c.matches_entity(FilterEntity::new(entity, c.reads())))
+        };
+        self.within_tick(ticks, entity) && predicates() && customs()
+    }
 }
 // TODO(perf): Likely can avoid O(n²). If only `ComponedId`s were
 // ordered in `Archetype::components()`…
@@ -219,14 +321,12 @@ impl<'a> InclusiveFilter<'a> {
     #[inline]
     pub fn all_included(self, ids: impl Iterator<Item = ComponentId>) -> bool {
         let mut found = Bitset(Vec::with_capacity(self.0.len()));
-        let mut max = 0;
         for id in ids {
             if let Some(idx) = self.0.iter().position(|x| x.id() == id) {
                 found.enable_bit_extending(idx);
-                max = max.max(idx as usize);
             }
         }
-        found.ones_in_range(..max).all_one()
+        found.ones().count() == self.0.len()
     }
 }
 impl<'a> ExclusiveFilter<'a> {
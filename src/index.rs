@@ -0,0 +1,73 @@
+//! [`ArchetypeIndex`], an inverted index from components to archetypes.
+use bevy_ecs::archetype::Archetypes;
+use bevy_ecs::component::ComponentId;
+use datazoo::Bitset;
+
+/// An inverted index from [`ComponentId`] to the archetypes containing it.
+///
+/// With it, matching a new [`DynamicState`](crate::DynamicState) with
+/// archetypes is a series of bitset intersections rather than a scan of all
+/// archetypes' components. See [`DynamicState::new_indexed`](crate::DynamicState::new_indexed).
+///
+/// A single index can be shared between all states of the same world.
+/// [`DynamicQueryCache`](crate::DynamicQueryCache) maintains one.
+#[derive(Clone, Debug, Default)]
+pub struct ArchetypeIndex {
+    /// The archetypes with the component, indexed by `ComponentId::index`.
+    components: Vec<Bitset<Vec<u32>>>,
+    last_gen: usize,
+}
+impl ArchetypeIndex {
+    pub fn new(archetypes: &Archetypes) -> Self {
+        let mut index = Self::default();
+        index.update(archetypes);
+        index
+    }
+    /// Add to the index archetypes added to `archetypes` since last update.
+    pub fn update(&mut self, archetypes: &Archetypes) {
+        for (i, archetype) in archetypes.iter().enumerate().skip(self.last_gen) {
+            for id in archetype.components() {
+                if self.components.len() <= id.index() {
+                    self.components.resize_with(id.index() + 1, Bitset::default);
+                }
+                self.components[id.index()].enable_bit_extending(i);
+            }
+        }
+        self.last_gen = archetypes.len();
+    }
+    /// How many archetypes are in the index.
+    pub fn len(&self) -> usize {
+        self.last_gen
+    }
+    pub fn is_empty(&self) -> bool {
+        self.last_gen == 0
+    }
+    fn blocks(&self, id: ComponentId) -> &[u32] {
+        self.components.get(id.index()).map_or(&[], |set| &set.0)
+    }
+    /// The archetypes with all `required` and none of `excluded` components.
+    pub(crate) fn matching(
+        &self,
+        required: impl Iterator<Item = ComponentId>,
+        excluded: impl Iterator<Item = ComponentId>,
+    ) -> Bitset<Vec<u32>> {
+        let mut matching = vec![u32::MAX; self.last_gen.div_ceil(32)];
+        if let Some(last) = matching.last_mut() {
+            let extra_bits = (32 - self.last_gen % 32) % 32;
+            *last >>= extra_bits;
+        }
+        for id in required {
+            let blocks = self.blocks(id);
+            for (i, block) in matching.iter_mut().enumerate() {
+                *block &= blocks.get(i).copied().unwrap_or(0);
+            }
+        }
+        for id in excluded {
+            let blocks = self.blocks(id);
+            for (i, block) in matching.iter_mut().enumerate() {
+                *block &= !blocks.get(i).copied().unwrap_or(0);
+            }
+        }
+        Bitset(matching)
+    }
+}
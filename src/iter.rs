@@ -1,33 +1,47 @@
+use std::slice;
+
 use bevy_ecs::archetype::ArchetypeEntity;
+use bevy_ecs::prelude::Entity;
 use bevy_ecs::world::unsafe_world_cell::{UnsafeEntityCell, UnsafeWorldCell};
 
-use crate::archematch::{ArchematchIter, CheckTick};
+use crate::archematch::{ArchematchGet, ArchematchIter, CheckTick};
 use crate::debug_unchecked::DebugUnchecked;
 use crate::state::Ticks;
-use crate::{fetches::Fetches, filters::Filters, DynamicItem, DynamicState};
+use crate::{DynamicRow, DynamicState};
 
-fn fetch_buffer_ro<'w>(fetches: &Fetches, entity: UnsafeEntityCell<'w>) -> Box<[DynamicItem<'w>]> {
+pub(crate) fn fetch_buffer_ro<'w, 's>(
+    state: &'s DynamicState,
+    entity: UnsafeEntityCell<'w>,
+) -> DynamicRow<'w, 's> {
+    let fetches = &state.fetches;
     let mut item_buffer = Vec::with_capacity(fetches.len());
     for fetch in unsafe { fetches.iter_read_only(entity) } {
         item_buffer.push(fetch);
     }
-    item_buffer.into()
+    state.row_index.init_names(entity.world().components());
+    DynamicRow::new(item_buffer.into(), state)
 }
-fn fetch_buffer<'w>(fetches: &Fetches, entity: UnsafeEntityCell<'w>) -> Box<[DynamicItem<'w>]> {
+pub(crate) fn fetch_buffer<'w, 's>(
+    state: &'s DynamicState,
+    entity: UnsafeEntityCell<'w>,
+) -> DynamicRow<'w, 's> {
+    let fetches = &state.fetches;
     let mut item_buffer = Vec::with_capacity(fetches.len());
     for fetch in unsafe { fetches.iter(entity) } {
         item_buffer.push(fetch);
     }
-    item_buffer.into()
+    state.row_index.init_names(entity.world().components());
+    DynamicRow::new(item_buffer.into(), state)
 }
 pub struct RoDynamicQueryIter<'w, 's> {
+    /// Entities in a [`crate::StableOrder`], if the state has one.
+    ordered: Option<(slice::Iter<'s, Entity>, ArchematchGet<'s>)>,
     entities: &'w [ArchetypeEntity],
     world: UnsafeWorldCell<'w>,
-    fetch: &'s Fetches,
-    filter: &'s Filters,
+    state: &'s DynamicState,
     ids: ArchematchIter<'s>,
     check: CheckTick,
-    buffer: Option<Box<[DynamicItem<'w>]>>,
+    buffer: Option<DynamicRow<'w, 's>>,
     ticks: Ticks,
 }
 impl<'w, 's> RoDynamicQueryIter<'w, 's> {
@@ -38,11 +52,16 @@ impl<'w, 's> RoDynamicQueryIter<'w, 's> {
     ///
     /// Returns `None` if we exhausted all entities present in `query_archetypes`.
     fn next_entity(&mut self) -> Option<UnsafeEntityCell<'w>> {
+        if let Some((entities, get)) = &mut self.ordered {
+            let (world, ticks) = (self.world, self.ticks);
+            return entities.find_map(|&entity| {
+                let entity = world.get_entity(entity)?;
+                get.contains(ticks, entity).then_some(entity)
+            });
+        }
         loop {
             let Some((first, remaining)) = self.entities.split_first() else {
-                let Some((next_archetype, check)) = self.ids.next() else {
-                    return None;
-                };
+                let (next_archetype, check) = self.ids.next()?;
                 let archetype = self.world.archetypes().get(next_archetype);
                 let archetype = unsafe { archetype.prod_unchecked_unwrap() };
                 self.check = check;
@@ -60,71 +79,97 @@ impl<'w, 's> RoDynamicQueryIter<'w, 's> {
             }
         }
     }
-    pub fn new(world: UnsafeWorldCell<'w>, state: &'s DynamicState) -> Self {
-        let mut this = Self {
+    /// An iterator that didn't fetch its first item yet.
+    fn empty(world: UnsafeWorldCell<'w>, state: &'s DynamicState, ticks: Ticks) -> Self {
+        let ordered = state.order.as_ref().map(|order| {
+            let get = state.archetype_ids.getter(&state.filters);
+            (order.entities.iter(), get)
+        });
+        RoDynamicQueryIter {
+            ordered,
             ids: state.archetype_ids.iter(&state.filters),
             check: CheckTick::default(),
-            filter: &state.filters,
-            fetch: &state.fetches,
+            state,
             entities: &[][..],
             buffer: None,
             world,
-            ticks: Ticks {
-                last_run: world.last_change_tick(),
-                this_run: world.change_tick(),
-            },
-        };
+            ticks,
+        }
+    }
+    /// # Safety
+    /// `world` must have read access to all components fetched by `state`.
+    pub(crate) unsafe fn new(
+        world: UnsafeWorldCell<'w>,
+        state: &'s DynamicState,
+        ticks: Ticks,
+    ) -> Self {
+        let mut this = Self::empty(world, state, ticks);
         if let Some(next_entity) = this.next_entity() {
-            this.buffer = Some(fetch_buffer_ro(this.fetch, next_entity));
+            this.buffer = Some(fetch_buffer_ro(this.state, next_entity));
         }
         this
     }
 }
+/// The entities matching a [`DynamicState`], without fetching their components.
+pub(crate) struct MatchedEntities<'w, 's>(RoDynamicQueryIter<'w, 's>);
+impl<'w, 's> MatchedEntities<'w, 's> {
+    /// # Safety
+    /// `world` must have read access to the components of `state`'s filters.
+    pub(crate) unsafe fn new(
+        world: UnsafeWorldCell<'w>,
+        state: &'s DynamicState,
+        ticks: Ticks,
+    ) -> Self {
+        MatchedEntities(RoDynamicQueryIter::empty(world, state, ticks))
+    }
+}
+impl<'w> Iterator for MatchedEntities<'w, '_> {
+    type Item = UnsafeEntityCell<'w>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next_entity()
+    }
+}
 impl<'w, 's> Iterator for RoDynamicQueryIter<'w, 's> {
     // TODO(perf): Get rid of individual allocation per iteration.
-    type Item = Box<[DynamicItem<'w>]>;
+    type Item = DynamicRow<'w, 's>;
 
     fn next(&mut self) -> Option<Self::Item> {
         let ret = self.buffer.take()?;
         let Some(entity) = self.next_entity() else {
             return Some(ret);
         };
-        self.buffer = Some(fetch_buffer_ro(self.fetch, entity));
+        self.buffer = Some(fetch_buffer_ro(self.state, entity));
         Some(ret)
     }
 }
 
 pub struct DynamicQueryIter<'w, 's>(RoDynamicQueryIter<'w, 's>);
 impl<'w, 's> DynamicQueryIter<'w, 's> {
-    pub fn new(world: UnsafeWorldCell<'w>, state: &'s DynamicState) -> Self {
-        let mut this = RoDynamicQueryIter {
-            ids: state.archetype_ids.iter(&state.filters),
-            check: CheckTick::default(),
-            filter: &state.filters,
-            fetch: &state.fetches,
-            entities: &[][..],
-            buffer: None,
-            world,
-            ticks: Ticks {
-                last_run: world.last_change_tick(),
-                this_run: world.change_tick(),
-            },
-        };
+    /// # Safety
+    /// `world` must have mutable access to all components mutably fetched by
+    /// `state`, and read access to the rest.
+    pub(crate) unsafe fn new(
+        world: UnsafeWorldCell<'w>,
+        state: &'s DynamicState,
+        ticks: Ticks,
+    ) -> Self {
+        let mut this = RoDynamicQueryIter::empty(world, state, ticks);
         if let Some(next_entity) = this.next_entity() {
-            this.buffer = Some(fetch_buffer(this.fetch, next_entity));
+            this.buffer = Some(fetch_buffer(this.state, next_entity));
         }
         Self(this)
     }
 }
 impl<'w, 's> Iterator for DynamicQueryIter<'w, 's> {
-    type Item = Box<[DynamicItem<'w>]>;
+    type Item = DynamicRow<'w, 's>;
 
     fn next(&mut self) -> Option<Self::Item> {
         let ret = self.0.buffer.take()?;
         let Some(entity) = self.0.next_entity() else {
             return Some(ret);
         };
-        self.0.buffer = Some(fetch_buffer(self.0.fetch, entity));
+        self.0.buffer = Some(fetch_buffer(self.0.state, entity));
         Some(ret)
     }
 }
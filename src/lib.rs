@@ -1,6 +1,26 @@
-pub use builder::{AndFilter, AndFilters, DQuery, DynamicQueryBuilder, Fetch, OrFilters};
-pub use dynamic_query::{DynamicItem, DynamicQuery};
-pub use state::{DynamicState, Ticks};
+pub use aggregate::{Aggregate, AggregateError, AggregateGroup};
+pub use builder::{
+    AndFilter, AndFilters, DQuery, DynamicQueryBuilder, Fetch, FetchKind, OrFilters,
+};
+pub use cache::{update_dynamic_query_cache, DynamicQueryCache, DynamicQueryHandle};
+pub use cursor::DynamicCursor;
+pub use description::{DynamicQueryDescription, FetchDescription, FilterDescription, ResolveError};
+pub use dynamic_filter::{DynamicFilter, FilterEntity};
+pub use dynamic_query::{DynamicItem, DynamicQuery, RebindError};
+#[cfg(feature = "serde")]
+pub use export::{SnapshotDeserializer, SnapshotSerializer};
+pub use field::FieldError;
+pub use index::ArchetypeIndex;
+pub use order::StableOrder;
+pub use param::DynamicQueryParam;
+pub use param_set::DynamicParamSet;
+pub use predicate::{CompareOp, Predicate, PredicateError};
+pub use resources::{DynamicResources, DynamicResourcesState};
+pub use row::{DynamicRow, ExtractError, RowKey};
+pub use snapshot::{Snapshot, SnapshotRow, SnapshotValue};
+pub use sort::SortOrder;
+pub use state::{DynamicQueryError, DynamicState, Ticks};
+pub use system::DynamicSystem;
 
 /// Panic in debug mode, assume `true` in release mode.
 macro_rules! assert_invariant {
@@ -12,16 +32,40 @@ macro_rules! assert_invariant {
     }};
 }
 
+mod access;
+pub mod aggregate;
 mod archematch;
 pub mod builder;
+mod cache;
+mod cursor;
 mod debug_unchecked;
+mod description;
+pub mod dynamic_filter;
 mod dynamic_query;
+pub mod explain;
+#[cfg(feature = "serde")]
+pub mod export;
 mod fetches;
+mod field;
 mod filters;
+mod index;
 mod iter;
 mod maybe_item;
+pub mod order;
+mod param;
+mod param_set;
+#[cfg(feature = "parse")]
+pub mod parse;
+pub mod predicate;
 pub mod pretty_print;
+mod resources;
+pub mod row;
+#[cfg(feature = "rand")]
+mod sample;
+pub mod snapshot;
+pub mod sort;
 mod state;
+mod system;
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,192 @@
+//! Iterate a [`DynamicState`] in an order that doesn't depend on the spawn
+//! history of the world.
+//!
+//! See [`DynamicState::set_stable_order`].
+use bevy_ecs::component::Tick;
+use bevy_ecs::prelude::Entity;
+use bevy_ecs::world::unsafe_world_cell::UnsafeWorldCell;
+use bevy_ecs::world::World;
+use bevy_utils::{HashMap, HashSet};
+
+use crate::archematch::archetype_id_to_u32;
+use crate::field::{FieldError, FieldPath};
+use crate::sort::{SortKey, SortOrder};
+use crate::DynamicState;
+
+/// The order in which a [`DynamicState`] iterates its entities, see
+/// [`DynamicState::set_stable_order`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StableOrder {
+    /// By [`Entity`] generation then index.
+    Entity,
+    /// By the value at a path, as in [`DynamicState::iter_sorted_by`], then
+    /// by [`Entity`].
+    Key(String),
+}
+
+/// The entities of a [`DynamicState`] in a [`StableOrder`].
+#[derive(Debug, Clone)]
+pub(crate) struct EntityOrder {
+    order: StableOrder,
+    /// Entities of the matched archetypes, in `order`.
+    pub(crate) entities: Vec<Entity>,
+    /// The entities of each matched archetype as of the last update, to find
+    /// entities that started or stopped matching without sorting again.
+    seen: HashMap<u32, Vec<Entity>>,
+    /// The change tick of the last update, to find entities with changed keys.
+    last_update: Option<Tick>,
+}
+impl EntityOrder {
+    fn new(order: StableOrder) -> Self {
+        EntityOrder {
+            order,
+            entities: Vec::new(),
+            seen: HashMap::default(),
+            last_update: None,
+        }
+    }
+}
+
+/// Merge the sorted `old` and `new`, keeping `old` first on ties.
+fn merge<T>(old: Vec<T>, new: Vec<T>, is_less: impl Fn(&T, &T) -> bool) -> Vec<T> {
+    let mut merged = Vec::with_capacity(old.len() + new.len());
+    let (mut old, mut new) = (old.into_iter().peekable(), new.into_iter().peekable());
+    while let (Some(o), Some(n)) = (old.peek(), new.peek()) {
+        let next = if is_less(n, o) { new.next() } else { old.next() };
+        merged.extend(next);
+    }
+    merged.extend(old);
+    merged.extend(new);
+    merged
+}
+
+impl DynamicState {
+    /// Iterate entities in `order` rather than in archetype order,
+    /// `None` to go back to archetype order.
+    ///
+    /// The order is kept up to date by [`Self::iter`], [`Self::iter_mut`]
+    /// and [`crate::DynamicQueryParam::new`]. Other iteration methods use the
+    /// order as of the last update, see [`Self::update_order`].
+    ///
+    /// Updating the order compares the entities of each matched archetype
+    /// with the previous update, and only sorts entities that started
+    /// matching since then. With [`StableOrder::Key`], entities whose key
+    /// component changed are sorted again as well. Sorted entities are then
+    /// merged with the rest in linear time.
+    ///
+    /// # Errors
+    /// If `order` is a [`StableOrder::Key`] with a path that doesn't point to
+    /// a fetched component or a field of one.
+    pub fn set_stable_order(
+        &mut self,
+        world: &World,
+        order: Option<StableOrder>,
+    ) -> Result<(), FieldError> {
+        if let Some(StableOrder::Key(path)) = &order {
+            FieldPath::new(&self.fetches, world.components(), path)?;
+        }
+        self.order = order.map(EntityOrder::new);
+        self.update_order(world);
+        Ok(())
+    }
+    pub fn stable_order(&self) -> Option<&StableOrder> {
+        self.order.as_ref().map(|order| &order.order)
+    }
+    /// Update the iteration order with the entities of `world`, if this state
+    /// has a [`StableOrder`].
+    pub fn update_order(&mut self, world: &World) {
+        // SAFETY: we have read access to the whole world.
+        unsafe { self.update_order_manual(world.as_unsafe_world_cell_readonly()) }
+    }
+    /// Same as [`Self::update_order`].
+    ///
+    /// # Safety
+    /// `world` must have read access to the components fetched by this state.
+    pub unsafe fn update_order_manual(&mut self, world: UnsafeWorldCell) {
+        let Some(order) = &mut self.order else {
+            return;
+        };
+        let archetypes = world.archetypes();
+
+        // Entities that left or joined each matched archetype.
+        let mut removed: HashSet<Entity> = HashSet::default();
+        let mut added = Vec::new();
+        for (id, _) in self.archetype_ids.iter(&self.filters) {
+            let Some(archetype) = archetypes.get(id) else {
+                continue;
+            };
+            let current = archetype.entities();
+            let seen = order.seen.entry(archetype_id_to_u32(id)).or_default();
+            let unchanged = seen.iter().zip(current).all(|(s, c)| *s == c.entity());
+            if unchanged && seen.len() <= current.len() {
+                // Spawned entities are appended to their archetype.
+                let spawned = current[seen.len()..].iter().map(|e| e.entity());
+                seen.extend(spawned.clone());
+                added.extend(spawned);
+                continue;
+            }
+            let previous: HashSet<_> = seen.iter().copied().collect();
+            seen.clear();
+            seen.extend(current.iter().map(|e| e.entity()));
+            let current: HashSet<_> = seen.iter().copied().collect();
+            removed.extend(previous.difference(&current));
+            added.extend(current.difference(&previous));
+        }
+
+        let this_update = world.change_tick();
+        let field = match &order.order {
+            StableOrder::Entity => None,
+            StableOrder::Key(path) => {
+                let field = FieldPath::new(&self.fetches, world.components(), path);
+                Some(field.expect("The path was checked in `set_stable_order`"))
+            }
+        };
+        // Entities with a key changed since the last update, included.
+        let mut changed: Vec<Entity> = Vec::new();
+        if let (Some(field), Some(last_update)) = (&field, order.last_update) {
+            let since = Tick::new(last_update.get().wrapping_sub(1));
+            let is_changed = |entity: &Entity| {
+                let entity = world.get_entity(*entity);
+                // SAFETY: `world` has read access to the fetched components.
+                let ticks = entity.and_then(|e| unsafe { e.get_change_ticks_by_id(field.id()) });
+                ticks.is_some_and(|t| t.is_changed(since, this_update))
+            };
+            let entities = order.entities.iter().filter(|e| !removed.contains(*e));
+            changed.extend(entities.filter(|e| is_changed(e)));
+        }
+        order.last_update = Some(this_update);
+
+        if removed.is_empty() && added.is_empty() && changed.is_empty() {
+            return;
+        }
+        let stale: HashSet<_> = removed.iter().chain(&changed).copied().collect();
+        order.entities.retain(|entity| !stale.contains(entity));
+        // An entity that moved between matched archetypes is both removed and
+        // added, don't add it twice if its key changed too.
+        let changed = changed.into_iter().filter(|e| !removed.contains(e));
+        added.extend(changed);
+
+        let old = std::mem::take(&mut order.entities);
+        order.entities = match &field {
+            None => {
+                added.sort_unstable();
+                merge(old, added, |l, r| l < r)
+            }
+            Some(field) => {
+                let keys = |entities: Vec<Entity>| {
+                    let keys = entities.into_iter().filter_map(|entity| {
+                        let entity = world.get_entity(entity)?;
+                        // SAFETY: `world` has read access to the fetched components.
+                        Some(SortKey { value: unsafe { field.get(entity) }, entity })
+                    });
+                    keys.collect::<Vec<_>>()
+                };
+                let mut added = keys(added);
+                added.sort_by(|l, r| l.cmp(r, SortOrder::Ascending));
+                let is_less = |l: &SortKey, r: &SortKey| l.cmp(r, SortOrder::Ascending).is_lt();
+                let merged = merge(keys(old), added, is_less);
+                merged.into_iter().map(|key| key.entity.id()).collect()
+            }
+        };
+    }
+}
@@ -0,0 +1,69 @@
+//! [`DynamicQueryParam`], the dynamic equivalent of a bevy `Query` system parameter.
+//!
+//! Note that bevy `0.11` doesn't let third party crates register world access
+//! in a `SystemParam`, so [`DynamicQueryParam`] cannot be used directly as a
+//! system parameter. Instead, a system that knows its [`DynamicState`] access
+//! can create one with [`DynamicQueryParam::new`].
+use bevy_ecs::prelude::Entity;
+use bevy_ecs::world::unsafe_world_cell::UnsafeWorldCell;
+
+use crate::iter::{DynamicQueryIter, RoDynamicQueryIter};
+use crate::state::DynamicQueryError;
+use crate::{DynamicRow, DynamicState, Ticks};
+
+/// A [`DynamicState`] bound to a world and a set of [`Ticks`].
+///
+/// Unlike [`DynamicState::iter_mut`] and [`DynamicState::get_mut`], this
+/// doesn't require exclusive access to the world, only to the components
+/// declared in [`DynamicState::component_access`].
+pub struct DynamicQueryParam<'w, 's> {
+    world: UnsafeWorldCell<'w>,
+    state: &'s mut DynamicState,
+    ticks: Ticks,
+}
+impl<'w, 's> DynamicQueryParam<'w, 's> {
+    /// Create a [`DynamicQueryParam`], `ticks` are used for `Added` and
+    /// `Changed` filters.
+    ///
+    /// # Safety
+    /// - `world` must have access to the [`DynamicState::archetype_component_access`]
+    ///   of `state`, and no one else must be accessing it conflictingly for `'w`.
+    /// - `state` must have been updated with [`DynamicState::add_archetypes`]
+    ///   with the archetypes of `world`.
+    pub unsafe fn new(
+        world: UnsafeWorldCell<'w>,
+        state: &'s mut DynamicState,
+        ticks: Ticks,
+    ) -> Self {
+        // SAFETY: `world` has access to the components fetched by `state`.
+        unsafe { state.update_order_manual(world) };
+        DynamicQueryParam { world, state, ticks }
+    }
+    pub fn state(&self) -> &DynamicState {
+        self.state
+    }
+    pub fn get(&mut self, entity: Entity) -> Result<DynamicRow<'_, '_>, DynamicQueryError> {
+        // SAFETY: `Self::new` invariants guarentee we have access to the fetches
+        unsafe {
+            self.state
+                .get_read_only_manual(self.world, entity, self.ticks)
+        }
+    }
+    pub fn get_mut(&mut self, entity: Entity) -> Result<DynamicRow<'_, '_>, DynamicQueryError> {
+        // SAFETY: `Self::new` invariants guarentee we have access to the fetches,
+        // items cannot outlive `self`, so they are unique.
+        unsafe {
+            self.state
+                .get_unchecked_manual(self.world, entity, self.ticks)
+        }
+    }
+    pub fn iter(&self) -> RoDynamicQueryIter<'_, '_> {
+        // SAFETY: `Self::new` invariants guarentee we have access to the fetches
+        unsafe { self.state.iter_read_only_manual(self.world, self.ticks) }
+    }
+    pub fn iter_mut(&mut self) -> DynamicQueryIter<'_, '_> {
+        // SAFETY: `Self::new` invariants guarentee we have access to the fetches,
+        // items cannot outlive `self`, so they are unique.
+        unsafe { self.state.iter_unchecked_manual(self.world, self.ticks) }
+    }
+}
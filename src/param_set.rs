@@ -0,0 +1,141 @@
+//! [`DynamicParamSet`], several potentially conflicting [`DynamicState`]s.
+use bevy_ecs::archetype::{ArchetypeComponentId, Archetypes};
+use bevy_ecs::query::Access;
+use bevy_ecs::world::{unsafe_world_cell::UnsafeWorldCell, World};
+use datazoo::Bitset;
+
+use crate::{DynamicQuery, DynamicQueryParam, DynamicState, Ticks};
+
+/// A set of [`DynamicState`]s which access may conflict with each other.
+///
+/// Like bevy's `ParamSet`, conflicting states can only be borrowed one at a
+/// time, with [`DynamicParamSet::p`]. States that `With` and `Without`
+/// filters prove disjoint can be borrowed at the same time with
+/// [`DynamicParamSet::get_many`].
+#[derive(Clone, Debug)]
+pub struct DynamicParamSet {
+    states: Vec<DynamicState>,
+    /// Bit at `i * states.len() + j` is enabled if state `i` conflicts with `j`.
+    conflicts: Bitset<Box<[u32]>>,
+    archetype_component_access: Access<ArchetypeComponentId>,
+}
+impl DynamicParamSet {
+    pub fn new(queries: &[DynamicQuery], world: &mut World) -> Self {
+        let states = queries.iter().map(|query| query.state(world));
+        Self::from_states(states.collect())
+    }
+    pub fn from_states(states: Vec<DynamicState>) -> Self {
+        let len = states.len();
+        let mut conflicts = Bitset(vec![0; (len * len).div_ceil(32)].into_boxed_slice());
+        let mut archetype_component_access = Access::default();
+        for (i, state) in states.iter().enumerate() {
+            archetype_component_access.extend(state.archetype_component_access());
+            for (j, other) in states.iter().enumerate().skip(i + 1) {
+                if !state.is_compatible(other) {
+                    conflicts.enable_bit(i * len + j);
+                    conflicts.enable_bit(j * len + i);
+                }
+            }
+        }
+        DynamicParamSet { states, conflicts, archetype_component_access }
+    }
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+    pub fn states(&self) -> &[DynamicState] {
+        &self.states
+    }
+    /// Whether the `a`th and `b`th states have conflicting access.
+    ///
+    /// Note that a state never conflicts with itself.
+    ///
+    /// # Panics
+    /// If `a` or `b` is out of bound.
+    pub fn conflicts(&self, a: usize, b: usize) -> bool {
+        let len = self.len();
+        assert!(
+            a < len && b < len,
+            "state index out of bound: ({a}, {b}), len is {len}"
+        );
+        self.conflicts.bit(a * len + b)
+    }
+    /// Update all states with the new archetypes in `archetypes`.
+    pub fn add_archetypes(&mut self, archetypes: &Archetypes) {
+        for state in &mut self.states {
+            state.add_archetypes(archetypes);
+            let access = state.archetype_component_access();
+            self.archetype_component_access.extend(access);
+        }
+    }
+    /// The union of the archetype component access of all states.
+    pub fn archetype_component_access(&self) -> &Access<ArchetypeComponentId> {
+        &self.archetype_component_access
+    }
+
+    /// Borrow the `index`th state.
+    ///
+    /// # Panics
+    /// If `index` is out of bound.
+    pub fn p<'w>(&mut self, world: &'w mut World, index: usize) -> DynamicQueryParam<'w, '_> {
+        let world = world.as_unsafe_world_cell();
+        // SAFETY: we have exclusive access to the world.
+        unsafe { self.p_unchecked_manual(world, index, Ticks::of_world(world)) }
+    }
+    /// Borrow all the states at `indices` at the same time.
+    ///
+    /// Returns `None` if any two of them conflict, if an index is repeated
+    /// or out of bound.
+    pub fn get_many<'w, const N: usize>(
+        &mut self,
+        world: &'w mut World,
+        indices: [usize; N],
+    ) -> Option<[DynamicQueryParam<'w, '_>; N]> {
+        let world = world.as_unsafe_world_cell();
+        // SAFETY: we have exclusive access to the world.
+        unsafe { self.get_many_unchecked_manual(world, indices, Ticks::of_world(world)) }
+    }
+    /// Same as [`Self::p`], but with an arbitrary world access.
+    ///
+    /// # Safety
+    /// - `world` must have access to the [`Self::archetype_component_access`]
+    ///   of this set, and no one else must be accessing it conflictingly for `'w`.
+    /// - [`Self::add_archetypes`] must have been called with the archetypes of `world`.
+    pub unsafe fn p_unchecked_manual<'w>(
+        &mut self,
+        world: UnsafeWorldCell<'w>,
+        index: usize,
+        ticks: Ticks,
+    ) -> DynamicQueryParam<'w, '_> {
+        // SAFETY: caller upholds `DynamicQueryParam::new` invariants
+        unsafe { DynamicQueryParam::new(world, &mut self.states[index], ticks) }
+    }
+    /// Same as [`Self::get_many`], but with an arbitrary world access.
+    ///
+    /// # Safety
+    /// Same as [`Self::p_unchecked_manual`].
+    pub unsafe fn get_many_unchecked_manual<'w, const N: usize>(
+        &mut self,
+        world: UnsafeWorldCell<'w>,
+        indices: [usize; N],
+        ticks: Ticks,
+    ) -> Option<[DynamicQueryParam<'w, '_>; N]> {
+        for (i, &index) in indices.iter().enumerate() {
+            if index >= self.len() {
+                return None;
+            }
+            let mut previous = indices[..i].iter();
+            if previous.any(|&other| other == index || self.conflicts(other, index)) {
+                return None;
+            }
+        }
+        let states = self.states.as_mut_ptr();
+        // SAFETY:
+        // - indices are in bound and distinct, so each `&mut` is unique.
+        // - caller upholds `DynamicQueryParam::new` invariants, and we just
+        //   checked the states do not access the same components conflictingly.
+        Some(indices.map(|i| unsafe { DynamicQueryParam::new(world, &mut *states.add(i), ticks) }))
+    }
+}
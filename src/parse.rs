@@ -0,0 +1,631 @@
+//! Parse a [`DynamicQuery`] from text.
+//!
+//! Two syntaxes are accepted: a rust-like one, which reads like a bevy `Query`
+//! type, and a compact one, convenient to type in a console.
+//! [`DynamicQuery::display`] prints queries in either of those syntaxes.
+//!
+//! Component names are short type names, as in [`NamedDynamicBuilder`].
+//! `Entity` is a keyword, it can appear at any position in the fetches.
+//! Filters can be arbitrarily nested, they are converted into a list of
+//! conjunctions (the `Or` of `And`s [`DynamicQuery`] stores). Conjunctions
+//! that can never match, such as `(With<A>, Without<A>)`, are dropped, and
+//! filters implied by another filter of their conjunction are removed.
+//!
+//! # Rust-like grammar
+//!
+//! Trailing commas are accepted in tuples.
+//!
+//! ```ungrammar
+//! Query = 'Query' '<' Fetches (',' Filter)? '>'
+//! Fetches
+//!    = Fetch
+//!    | '(' (Fetch (',' Fetch)*)? ')'
+//! Fetch
+//!    = '&' 'ident'                // &ident
+//!    | '&mut' 'ident'             // &mut ident
+//!    | 'Option' '<' '&' 'ident' '>'    // Option<&ident>
+//!    | 'Option' '<' '&mut' 'ident' '>' // Option<&mut ident>
+//!    | 'Entity'
+//! Filter
+//!    = 'With' '<' 'ident' '>'
+//!    | 'Without' '<' 'ident' '>'
+//!    | 'Changed' '<' 'ident' '>'
+//!    | 'Added' '<' 'ident' '>'
+//!    | Predicate
+//!    | '(' Filter (',' Filter)* ')'        // All filters must match
+//!    | 'Or' '<' '(' Filter (',' Filter)* ')' '>' // Any filter must match
+//! ```
+//!
+//! Example:
+//!
+//! ```text
+//! Query<(Entity, &mut Health, Option<&Armor>), (Without<Invincible>, Or<(With<Player>, With<Enemy>)>)>
+//! ```
+//!
+//! # Predicates
+//!
+//! Both syntaxes accept a [`Predicate`] as a filter: a component name, an
+//! optional reflect path within it, a comparison operator and a RON literal,
+//! see [`Predicate::from_literal`].
+//!
+//! ```ungrammar
+//! Predicate = 'ident' Path? Op 'literal'
+//! Path = ('.' | '#' | '[') 'path'
+//! Op = '==' | '!=' | '<=' | '>=' | '<' | '>'
+//! ```
+//!
+//! In the compact syntax, a predicate has no whitespace: `Health.current<10`.
+//! So `A>B` is a predicate, write `A >B` for `With<A>` and `Changed<B>`.
+//! Rust-like predicates accept whitespace around the operator:
+//! `Health.current < 10`.
+//!
+//! Predicates created with [`Predicate::new`] have no literal, and queries
+//! with them or with a [`DynamicFilter`] can't be printed in a form this
+//! module can parse.
+//!
+//! [`DynamicFilter`]: crate::DynamicFilter
+//!
+//! # Compact grammar
+//!
+//! ```ungrammar
+//! Query = ('()' | Fetch+) (',' Or)?
+//! Fetch
+//!    = '?mut' 'ident' // Option<&mut ident>
+//!    | '?' 'ident'    // Option<&ident>
+//!    | 'mut' 'ident'  // &mut ident
+//!    | 'Entity'       // Entity
+//!    | 'ident'        // &ident
+//! Or = And ('|' And)*
+//! And = Filter+
+//! Filter
+//!    = '+' 'ident' // Added
+//!    | '>' 'ident' // Changed
+//!    | '!' 'ident' // Without
+//!    | Predicate
+//!    | 'ident'     // With
+//!    | '(' Or ')'
+//! ```
+//!
+//! Example, equivalent to the rust-like one:
+//!
+//! ```text
+//! Entity mut Health ?Armor, !Invincible (Player | Enemy)
+//! ```
+use std::ops::Range;
+
+use bevy_ecs::component::{ComponentId, Components};
+use bevy_reflect::{ReflectFromPtr, TypeRegistry};
+use thiserror::Error;
+use winnow::ascii::{alpha1, alphanumeric1, multispace0, multispace1};
+use winnow::combinator::{alt, cut_err, delimited, eof, not, opt, preceded, repeat};
+use winnow::combinator::{separated, terminated};
+use winnow::error::{ContextError, StrContext, StrContextValue};
+use winnow::token::{any, none_of, one_of, take_while};
+use winnow::{PResult, Parser};
+
+use crate::builder::{FetchData, NamedDynamicBuilder, NamedOrBuilder};
+use crate::predicate::{CompareOp, Predicate, PredicateError};
+use crate::DynamicQuery;
+
+/// A [`ParseError`] cause.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    #[error("Syntax error: {0}")]
+    Syntax(String),
+    #[error("No reflect-registered component named '{0}'.")]
+    UnknownComponent(String),
+    #[error("The component '{0}' is not registered with ReflectFromPtr, it cannot be fetched.")]
+    NotFetchable(String),
+    #[error("The query fetches the same component several times.")]
+    Duplicate,
+    #[error("The query filters can never match, they require and exclude the same component.")]
+    NeverMatches,
+    #[error(transparent)]
+    Predicate(#[from] PredicateError),
+}
+
+/// An error while parsing a [`DynamicQuery`], with the byte range in the
+/// input text where it occurred.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+#[error("{kind} (at {}..{})", span.start, span.end)]
+pub struct ParseError {
+    pub span: Range<usize>,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AndFilterStr<'a> {
+    With(&'a str),
+    Without(&'a str),
+    Changed(&'a str),
+    Added(&'a str),
+    /// A [`Predicate`] on the field at `path` of `component`, `path` has no
+    /// leading `.`.
+    Predicate {
+        component: &'a str,
+        path: &'a str,
+        op: CompareOp,
+        literal: &'a str,
+    },
+}
+impl<'a> AndFilterStr<'a> {
+    fn name(&self) -> &'a str {
+        use AndFilterStr::{Added, Changed, With, Without};
+        match *self {
+            With(name) | Without(name) | Changed(name) | Added(name) => name,
+            AndFilterStr::Predicate { component, .. } => component,
+        }
+    }
+    fn build(&self, builder: &mut NamedOrBuilder) {
+        match *self {
+            AndFilterStr::With(name) => builder.with(name),
+            AndFilterStr::Without(name) => builder.without(name),
+            AndFilterStr::Changed(name) => builder.changed(name),
+            AndFilterStr::Added(name) => builder.added(name),
+            AndFilterStr::Predicate { component, path, op, literal } => {
+                builder.predicate(component, path, op, literal)
+            }
+        };
+    }
+}
+/// A conjunction: all its filters must match.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AndFiltersStr<'a>(pub Vec<AndFilterStr<'a>>);
+impl<'a> AndFiltersStr<'a> {
+    /// This conjunction without the filters implied by another of its
+    /// filters, `None` if it can never match.
+    ///
+    /// `Added<A>` implies `Changed<A>`, which implies `With<A>`.
+    fn simplified(self) -> Option<Self> {
+        use AndFilterStr::{Added, Changed, With, Without};
+        let filters = &self.0;
+        let has = |filter: AndFilterStr<'a>| filters.contains(&filter);
+        let predicate_on = |name| {
+            filters.iter().any(|filter| match *filter {
+                AndFilterStr::Predicate { component, .. } => component == name,
+                _ => false,
+            })
+        };
+        let contradicts = |filter: &AndFilterStr<'a>| match *filter {
+            Without(name) => {
+                has(With(name)) || has(Changed(name)) || has(Added(name)) || predicate_on(name)
+            }
+            _ => false,
+        };
+        if filters.iter().any(contradicts) {
+            return None;
+        }
+        let implied = |filter: &&AndFilterStr<'a>| match **filter {
+            With(name) => has(Changed(name)) || has(Added(name)),
+            Changed(name) => has(Added(name)),
+            _ => false,
+        };
+        let filters = filters.iter().filter(|f| !implied(f)).copied().collect();
+        Some(AndFiltersStr(filters))
+    }
+
+    pub fn build(&self, builder: &mut NamedDynamicBuilder) {
+        builder.or(|builder| {
+            for filter in &self.0 {
+                filter.build(builder);
+            }
+            builder
+        });
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FetchStr<'a> {
+    Read(&'a str),
+    Mut(&'a str),
+    OptionRead(&'a str),
+    OptionMut(&'a str),
+    Entity,
+}
+impl<'a> FetchStr<'a> {
+    fn name(&self) -> Option<&'a str> {
+        use FetchStr::{Mut, OptionMut, OptionRead, Read};
+        match *self {
+            Read(name) | Mut(name) | OptionRead(name) | OptionMut(name) => Some(name),
+            FetchStr::Entity => None,
+        }
+    }
+    pub fn build(&self, builder: &mut NamedDynamicBuilder) {
+        match self {
+            FetchStr::Read(name) => builder.component(name),
+            FetchStr::Mut(name) => builder.component_mut(name),
+            FetchStr::OptionRead(name) => builder.optional(name),
+            FetchStr::OptionMut(name) => builder.optional_mut(name),
+            FetchStr::Entity => builder.entity(),
+        };
+    }
+}
+
+/// A parsed query, with component names not yet resolved.
+///
+/// Component names are sub-slices of the parsed text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueryStr<'a> {
+    pub fetches: Vec<FetchStr<'a>>,
+    /// The filters, as a list of conjunctions, any of which must match.
+    pub filters: Vec<AndFiltersStr<'a>>,
+}
+impl<'a> QueryStr<'a> {
+    /// Parse `input` as a query in either the rust-like or compact syntax.
+    ///
+    /// See the [module-level documentation](self) for the grammar.
+    pub fn parse(input: &'a str) -> Result<Self, ParseError> {
+        let mut query = delimited(multispace0, alt((rust_like::query, compact::query)), eof);
+        let (fetches, filters) = query.parse(input).map_err(|err| {
+            let start = err.offset();
+            let next_char = input[start..].chars().next().map_or(0, char::len_utf8);
+            let kind = ParseErrorKind::Syntax(err.inner().to_string());
+            ParseError { span: start..start + next_char, kind }
+        })?;
+        let never_matches = ParseError {
+            span: 0..input.len(),
+            kind: ParseErrorKind::NeverMatches,
+        };
+        let filters = filters.map_or(Some(Vec::new()), FilterExpr::conjunctions);
+        let filters = filters.ok_or(never_matches)?;
+        Ok(QueryStr { fetches, filters })
+    }
+    /// Add this query's fetches and filters to `builder`.
+    ///
+    /// # Panics
+    ///
+    /// If a component name is not in `builder`'s registry, see [`Self::check`]
+    /// to avoid it.
+    pub fn build(&self, builder: &mut NamedDynamicBuilder) {
+        for fetch in &self.fetches {
+            fetch.build(builder);
+        }
+        for conjunction in &self.filters {
+            conjunction.build(builder);
+        }
+    }
+    /// Check all component names in this query exist in `registry` and
+    /// `components`, and that no component is fetched twice.
+    ///
+    /// `input` is the text this query was parsed from, used to compute error spans.
+    pub fn check(
+        &self,
+        input: &str,
+        registry: &TypeRegistry,
+        components: &Components,
+    ) -> Result<(), ParseError> {
+        let span = |name: &str| {
+            let start = name.as_ptr() as usize - input.as_ptr() as usize;
+            start..start + name.len()
+        };
+        let error = |name: &str, kind| Err(ParseError { span: span(name), kind });
+
+        for name in self.fetches.iter().filter_map(FetchStr::name) {
+            match registry.get_with_short_name(name) {
+                Some(reg) if reg.data::<ReflectFromPtr>().is_none() => {
+                    return error(name, ParseErrorKind::NotFetchable(name.to_owned()));
+                }
+                _ => {}
+            }
+        }
+        let filters = self
+            .filters
+            .iter()
+            .flat_map(|c| &c.0)
+            .map(AndFilterStr::name);
+        for name in self
+            .fetches
+            .iter()
+            .filter_map(FetchStr::name)
+            .chain(filters)
+        {
+            if component_id(registry, components, name).is_none() {
+                return error(name, ParseErrorKind::UnknownComponent(name.to_owned()));
+            }
+        }
+        let predicates = self.filters.iter().flat_map(|c| &c.0);
+        for filter in predicates {
+            let AndFilterStr::Predicate { component, path, op, literal } = *filter else {
+                continue;
+            };
+            let registration = registry.get_with_short_name(component);
+            let from_ptr = registration.and_then(|r| r.data::<ReflectFromPtr>());
+            let Some(from_ptr) = from_ptr.cloned() else {
+                return error(
+                    component,
+                    ParseErrorKind::NotFetchable(component.to_owned()),
+                );
+            };
+            let id = component_id(registry, components, component);
+            // unwrap: ids of filter names were checked above.
+            let data = FetchData { id: id.unwrap(), from_ptr };
+            if let Err(err) = Predicate::from_literal(registry, data, path, op, literal) {
+                let span = span(component).start..span(literal).end;
+                return Err(ParseError { span, kind: err.into() });
+            }
+        }
+        let fetched: Vec<_> = self.fetches.iter().filter_map(FetchStr::name).collect();
+        for (i, name) in fetched.iter().enumerate() {
+            if fetched[..i].contains(name) {
+                return error(name, ParseErrorKind::Duplicate);
+            }
+        }
+        Ok(())
+    }
+}
+fn component_id(
+    registry: &TypeRegistry,
+    components: &Components,
+    name: &str,
+) -> Option<ComponentId> {
+    let registration = registry.get_with_short_name(name)?;
+    components.get_id(registration.type_id())
+}
+
+impl DynamicQuery {
+    /// Parse a [`DynamicQuery`] from text, see the [`parse`](crate::parse)
+    /// module for the grammar.
+    ///
+    /// Component names are short type names, looked up in `registry`.
+    pub fn parse(
+        input: &str,
+        registry: &TypeRegistry,
+        components: &Components,
+    ) -> Result<Self, ParseError> {
+        let query = QueryStr::parse(input)?;
+        query.check(input, registry, components)?;
+
+        let mut builder = NamedDynamicBuilder::new(registry, components);
+        query.build(&mut builder);
+        builder.build().ok_or(ParseError {
+            span: 0..input.len(),
+            kind: ParseErrorKind::Duplicate,
+        })
+    }
+}
+
+/// The fetches and filters of a query, before conversion of the filters into
+/// a list of conjunctions.
+type ParsedQuery<'a> = (Vec<FetchStr<'a>>, Option<FilterExpr<'a>>);
+
+/// Arbitrarily nested filters, before conversion into a list of conjunctions.
+#[derive(Debug)]
+enum FilterExpr<'a> {
+    Filter(AndFilterStr<'a>),
+    And(Vec<FilterExpr<'a>>),
+    Or(Vec<FilterExpr<'a>>),
+}
+impl<'a> FilterExpr<'a> {
+    /// The conjunctions that can match, `None` if none can.
+    fn conjunctions(self) -> Option<Vec<AndFiltersStr<'a>>> {
+        let conjunctions = self.dnf().into_iter().filter_map(AndFiltersStr::simplified);
+        let conjunctions: Vec<_> = conjunctions.collect();
+        (!conjunctions.is_empty()).then_some(conjunctions)
+    }
+    /// Convert to disjunctive normal form, a list of conjunctions.
+    fn dnf(self) -> Vec<AndFiltersStr<'a>> {
+        match self {
+            FilterExpr::Filter(filter) => vec![AndFiltersStr(vec![filter])],
+            FilterExpr::Or(exprs) => exprs.into_iter().flat_map(Self::dnf).collect(),
+            FilterExpr::And(exprs) => {
+                let distribute = |lhs: Vec<AndFiltersStr<'a>>, expr: Self| {
+                    let rhs = expr.dnf();
+                    let product = lhs.iter().flat_map(|lhs| {
+                        rhs.iter().map(|rhs| {
+                            let mut conjunction = lhs.0.clone();
+                            for filter in &rhs.0 {
+                                if !conjunction.contains(filter) {
+                                    conjunction.push(*filter);
+                                }
+                            }
+                            AndFiltersStr(conjunction)
+                        })
+                    });
+                    product.collect()
+                };
+                exprs
+                    .into_iter()
+                    .fold(vec![AndFiltersStr(Vec::new())], distribute)
+            }
+        }
+    }
+}
+
+macro_rules! ws {
+    ($parser:expr) => {
+        delimited(multispace0, $parser, multispace0)
+    };
+}
+
+fn expected(description: &'static str) -> StrContext {
+    StrContext::Expected(StrContextValue::Description(description))
+}
+fn entity<'i>(input: &mut &'i str) -> PResult<FetchStr<'i>> {
+    let keyword = terminated("Entity", not(alt((alphanumeric1, "_"))));
+    keyword.map(|_| FetchStr::Entity).parse_next(input)
+}
+fn component<'i>(input: &mut &'i str) -> PResult<&'i str> {
+    let repeat = repeat::<_, _, (), _, _>;
+    (alt((alpha1, "_")), repeat(.., alt((alphanumeric1, "_"))))
+        .recognize()
+        .context(expected("component name"))
+        .parse_next(input)
+}
+
+fn compare_op(input: &mut &str) -> PResult<CompareOp> {
+    alt((
+        "==".value(CompareOp::Eq),
+        "!=".value(CompareOp::Ne),
+        "<=".value(CompareOp::Le),
+        ">=".value(CompareOp::Ge),
+        "<".value(CompareOp::Lt),
+        ">".value(CompareOp::Gt),
+    ))
+    .context(expected("comparison operator"))
+    .parse_next(input)
+}
+/// A reflect path right after a component name, such as `.current` or `#0`.
+///
+/// Returns the path without its leading `.`.
+fn field_path<'i>(input: &mut &'i str) -> PResult<&'i str> {
+    let is_path = |c: char| c.is_alphanumeric() || "_.#[]".contains(c);
+    let path = (one_of(['.', '#', '[']), take_while(0.., is_path)).recognize();
+    let path = opt(path).map(|path: Option<&str>| path.unwrap_or_default());
+    path.map(|path| path.strip_prefix('.').unwrap_or(path))
+        .parse_next(input)
+}
+/// A predicate, `ws` parses what may be between the path, operator and literal.
+fn predicate<'i>(
+    ws: fn(&mut &'i str) -> PResult<()>,
+) -> impl Parser<&'i str, AndFilterStr<'i>, ContextError> {
+    let op = delimited(ws, compare_op, ws);
+    (component, field_path, op, cut_err(literal)).map(|(component, path, op, literal)| {
+        AndFilterStr::Predicate { component, path, op, literal }
+    })
+}
+/// The text of a RON value. It stops at whitespace or one of `,|<>` outside
+/// of brackets and quotes.
+fn literal<'i>(input: &mut &'i str) -> PResult<&'i str> {
+    let word = take_while(1.., |c: char| {
+        !c.is_whitespace() && !"()[]{}<>|,\"'".contains(c)
+    });
+    let part = alt((quoted('"'), quoted('\''), group, word.void()));
+    repeat::<_, _, (), _, _>(1.., part)
+        .recognize()
+        .context(expected("literal"))
+        .parse_next(input)
+}
+fn quoted<'i>(quote: char) -> impl Parser<&'i str, (), ContextError> {
+    let escaped = preceded('\\', any);
+    let chars = repeat::<_, _, (), _, _>(0.., alt((escaped, none_of([quote, '\\']))));
+    (quote, chars, quote).void()
+}
+/// A balanced `()`, `[]` or `{}` group, possibly with whitespace inside.
+fn group(input: &mut &str) -> PResult<()> {
+    let inner = || {
+        let text = take_while(1.., |c: char| !"()[]{}\"'".contains(c)).void();
+        repeat::<_, _, (), _, _>(0.., alt((quoted('"'), quoted('\''), group, text)))
+    };
+    alt((
+        delimited('(', inner(), ')'),
+        delimited('[', inner(), ']'),
+        delimited('{', inner(), '}'),
+    ))
+    .parse_next(input)
+}
+
+mod compact {
+    use super::*;
+
+    pub(super) fn query<'i>(input: &mut &'i str) -> PResult<ParsedQuery<'i>> {
+        let no_fetches = "()".map(|_| Vec::new());
+        let fetches = alt((ws!(no_fetches), repeat(1.., ws!(fetch))));
+        let filters = opt(preceded(",", cut_err(ws!(or))));
+        (fetches, filters).parse_next(input)
+    }
+    fn fetch<'i>(input: &mut &'i str) -> PResult<FetchStr<'i>> {
+        alt((
+            entity,
+            preceded(("?mut", multispace1), component).map(FetchStr::OptionMut),
+            preceded(("mut", multispace1), component).map(FetchStr::Mut),
+            preceded("?", component).map(FetchStr::OptionRead),
+            component.map(FetchStr::Read),
+        ))
+        .context(StrContext::Label("fetch"))
+        .parse_next(input)
+    }
+    fn or<'i>(input: &mut &'i str) -> PResult<FilterExpr<'i>> {
+        let rest = repeat(0.., preceded("|", cut_err(ws!(and))));
+        (ws!(and), rest)
+            .map(|(first, mut rest): (_, Vec<_>)| {
+                rest.insert(0, first);
+                FilterExpr::Or(rest)
+            })
+            .parse_next(input)
+    }
+    fn and<'i>(input: &mut &'i str) -> PResult<FilterExpr<'i>> {
+        repeat(1.., ws!(filter))
+            .map(FilterExpr::And)
+            .parse_next(input)
+    }
+    fn filter<'i>(input: &mut &'i str) -> PResult<FilterExpr<'i>> {
+        let no_ws = |_: &mut &str| Ok(());
+        alt((
+            predicate(no_ws).map(FilterExpr::Filter),
+            preceded("!", component).map(|c| FilterExpr::Filter(AndFilterStr::Without(c))),
+            preceded("+", component).map(|c| FilterExpr::Filter(AndFilterStr::Added(c))),
+            preceded(">", component).map(|c| FilterExpr::Filter(AndFilterStr::Changed(c))),
+            component.map(|c| FilterExpr::Filter(AndFilterStr::With(c))),
+            delimited("(", cut_err(ws!(or)), cut_err(")").context(expected(")"))),
+        ))
+        .context(StrContext::Label("filter"))
+        .parse_next(input)
+    }
+}
+mod rust_like {
+    use super::*;
+
+    /// A `open` `inner` `close`, with optional whitespaces between each.
+    fn generic<'i, O>(
+        open: &'static str,
+        inner: impl Parser<&'i str, O, winnow::error::ContextError>,
+    ) -> impl Parser<&'i str, O, winnow::error::ContextError> {
+        delimited(
+            (open, ws!("<")),
+            cut_err(ws!(inner)),
+            cut_err(">").context(expected(">")),
+        )
+    }
+    /// A tuple of at least `min` `inner` elements, with optional trailing comma.
+    fn tuple<'i, O>(
+        min: usize,
+        inner: impl Parser<&'i str, O, winnow::error::ContextError>,
+    ) -> impl Parser<&'i str, Vec<O>, winnow::error::ContextError> {
+        let close = cut_err((opt(ws!(",")), ")")).context(expected(")"));
+        delimited("(", ws!(separated(min.., ws!(inner), ",")), close)
+    }
+
+    pub(super) fn query<'i>(input: &mut &'i str) -> PResult<ParsedQuery<'i>> {
+        let filters = opt(preceded(",", cut_err(ws!(filter))));
+        generic("Query", (ws!(fetches), filters)).parse_next(input)
+    }
+    fn fetches<'i>(input: &mut &'i str) -> PResult<Vec<FetchStr<'i>>> {
+        alt((tuple(0, fetch), fetch.map(|f| vec![f]))).parse_next(input)
+    }
+    fn fetch<'i>(input: &mut &'i str) -> PResult<FetchStr<'i>> {
+        let reference = || {
+            alt((
+                preceded(("&", multispace0, "mut", multispace1), component).map(FetchStr::Mut),
+                preceded(("&", multispace0), component).map(FetchStr::Read),
+            ))
+        };
+        let option = |fetch| match fetch {
+            FetchStr::Mut(name) => FetchStr::OptionMut(name),
+            FetchStr::Read(name) => FetchStr::OptionRead(name),
+            other => other,
+        };
+        alt((
+            reference(),
+            generic("Option", reference()).map(option),
+            entity,
+        ))
+        .context(StrContext::Label("fetch"))
+        .parse_next(input)
+    }
+    fn filter<'i>(input: &mut &'i str) -> PResult<FilterExpr<'i>> {
+        let single = |name, filter: fn(&'i str) -> AndFilterStr<'i>| {
+            generic(name, component).map(move |c| FilterExpr::Filter(filter(c)))
+        };
+        alt((
+            single("Without", AndFilterStr::Without),
+            single("With", AndFilterStr::With),
+            single("Changed", AndFilterStr::Changed),
+            single("Added", AndFilterStr::Added),
+            generic("Or", tuple(1, filter)).map(FilterExpr::Or),
+            tuple(1, filter).map(FilterExpr::And),
+            predicate(|i| multispace0.void().parse_next(i)).map(FilterExpr::Filter),
+        ))
+        .context(StrContext::Label("filter"))
+        .parse_next(input)
+    }
+}
@@ -0,0 +1,338 @@
+//! Filter entities on the value of a reflected component field.
+//!
+//! A [`Predicate`] compares the value at a [`ParsedPath`] within a component
+//! to a literal, for example `Health.current < 10`. Predicates are checked on
+//! each entity of the archetypes matched by the rest of the conjunction.
+#[cfg(feature = "parse")]
+use std::any::TypeId;
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use bevy_ecs::component::ComponentId;
+use bevy_ecs::world::unsafe_world_cell::UnsafeEntityCell;
+use bevy_reflect::{ParsedPath, Reflect, ReflectFromPtr};
+#[cfg(feature = "parse")]
+use bevy_reflect::{ReflectDeserialize, TypeRegistry};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::builder::FetchData;
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum PredicateError {
+    #[error("Invalid reflect path: {0}")]
+    Path(String),
+    #[error("The component {0:?} is not registered, can't tell the type of its fields.")]
+    Unregistered(ComponentId),
+    #[error("The field at '{0}' is not registered with ReflectDeserialize, it can't be parsed.")]
+    NotDeserializable(String),
+    #[error("Invalid literal: {0}")]
+    Literal(String),
+}
+
+/// How a [`Predicate`] compares the field value to its literal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+impl CompareOp {
+    /// Compare `field` to `value`, `false` if they can't be compared.
+    ///
+    /// `Eq` and `Ne` use [`Reflect::reflect_partial_eq`], the other operators
+    /// only support primitive numbers, `bool`, `char` and `String`.
+    pub fn compare(self, field: &dyn Reflect, value: &dyn Reflect) -> bool {
+        match self {
+            CompareOp::Eq => field.reflect_partial_eq(value) == Some(true),
+            CompareOp::Ne => field.reflect_partial_eq(value) == Some(false),
+            CompareOp::Lt => reflect_partial_cmp(field, value).is_some_and(Ordering::is_lt),
+            CompareOp::Le => reflect_partial_cmp(field, value).is_some_and(Ordering::is_le),
+            CompareOp::Gt => reflect_partial_cmp(field, value).is_some_and(Ordering::is_gt),
+            CompareOp::Ge => reflect_partial_cmp(field, value).is_some_and(Ordering::is_ge),
+        }
+    }
+}
+impl fmt::Display for CompareOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CompareOp::Eq => "==",
+            CompareOp::Ne => "!=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+        })
+    }
+}
+pub(crate) fn reflect_partial_cmp(field: &dyn Reflect, value: &dyn Reflect) -> Option<Ordering> {
+    macro_rules! cmp_as {
+        ($($ty:ty),*) => {$(
+            if let (Some(field), Some(value)) = (field.downcast_ref::<$ty>(), value.downcast_ref::<$ty>()) {
+                return field.partial_cmp(value);
+            }
+        )*};
+    }
+    cmp_as!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+    cmp_as!(f32, f64, bool, char, String);
+    None
+}
+/// A total order on values [`reflect_partial_cmp`] can compare, other values
+/// are `Equal`.
+fn canonical_value_cmp(left: &dyn Reflect, right: &dyn Reflect) -> Ordering {
+    macro_rules! total_cmp {
+        ($($ty:ty),*) => {$(
+            if let (Some(left), Some(right)) = (left.downcast_ref::<$ty>(), right.downcast_ref::<$ty>()) {
+                return left.total_cmp(right);
+            }
+        )*};
+    }
+    total_cmp!(f32, f64);
+    reflect_partial_cmp(left, right).unwrap_or(Ordering::Equal)
+}
+
+/// A step of a [`ParsedPath`], read from its `Display` text.
+#[cfg(feature = "parse")]
+#[derive(Clone, Copy)]
+enum PathStep<'a> {
+    Field(&'a str),
+    FieldIndex(usize),
+    TupleIndex(usize),
+    /// The index doesn't change the item type.
+    ListIndex,
+}
+/// The steps of `path`, which must be a valid [`ParsedPath`].
+#[cfg(feature = "parse")]
+fn path_steps(mut path: &str) -> impl Iterator<Item = PathStep<'_>> {
+    std::iter::from_fn(move || {
+        let (prefix, rest) = match path.chars().next()? {
+            prefix @ ('.' | '#' | '[') => (Some(prefix), &path[1..]),
+            _ => (None, path),
+        };
+        let end = rest.find(['.', '#', '[', ']']).unwrap_or(rest.len());
+        let (ident, rest) = rest.split_at(end);
+        path = rest.strip_prefix(']').unwrap_or(rest);
+        Some(match (prefix, ident.parse().ok()) {
+            (Some('#'), Some(index)) => PathStep::FieldIndex(index),
+            (Some('['), Some(_)) => PathStep::ListIndex,
+            (_, Some(index)) => PathStep::TupleIndex(index),
+            (_, None) => PathStep::Field(ident),
+        })
+    })
+}
+/// The type of the field at `path` in the type `type_id`, `None` if it has
+/// no such field or a type along `path` is not registered.
+///
+/// In enums, this is the type of the field in the first variant that has it.
+#[cfg(feature = "parse")]
+fn field_type_id(registry: &TypeRegistry, mut type_id: TypeId, path: &str) -> Option<TypeId> {
+    use bevy_reflect::{NamedField, TypeInfo, UnnamedField, VariantInfo};
+
+    for step in path_steps(path) {
+        type_id = match (step, registry.get_type_info(type_id)?) {
+            (PathStep::Field(name), TypeInfo::Struct(info)) => info.field(name)?.type_id(),
+            (PathStep::FieldIndex(i), TypeInfo::Struct(info)) => info.field_at(i)?.type_id(),
+            (PathStep::TupleIndex(i), TypeInfo::TupleStruct(info)) => info.field_at(i)?.type_id(),
+            (PathStep::TupleIndex(i), TypeInfo::Tuple(info)) => info.field_at(i)?.type_id(),
+            (PathStep::ListIndex, TypeInfo::List(info)) => info.item_type_id(),
+            (PathStep::ListIndex, TypeInfo::Array(info)) => info.item_type_id(),
+            (step, TypeInfo::Enum(info)) => {
+                let in_variant = |variant: &VariantInfo| match (step, variant) {
+                    (PathStep::Field(name), VariantInfo::Struct(v)) => {
+                        v.field(name).map(NamedField::type_id)
+                    }
+                    (PathStep::FieldIndex(i), VariantInfo::Struct(v)) => {
+                        v.field_at(i).map(NamedField::type_id)
+                    }
+                    (PathStep::TupleIndex(i), VariantInfo::Tuple(v)) => {
+                        v.field_at(i).map(UnnamedField::type_id)
+                    }
+                    _ => None,
+                };
+                info.iter().find_map(in_variant)?
+            }
+            _ => return None,
+        };
+    }
+    Some(type_id)
+}
+
+/// A condition on the value of a component field.
+///
+/// The predicate requires its component, entities without it never match.
+/// Add it to a conjunction with [`AndFilter::Predicate`](crate::AndFilter::Predicate).
+pub struct Predicate {
+    pub(crate) id: ComponentId,
+    from_ptr: ReflectFromPtr,
+    path: ParsedPath,
+    op: CompareOp,
+    value: Box<dyn Reflect>,
+    /// The text `value` was parsed from, if any.
+    literal: Option<Box<str>>,
+}
+impl Predicate {
+    /// Compare the field at `path` in the `component` to `value` with `op`.
+    ///
+    /// `value` should be of the same type as the field, otherwise the
+    /// predicate never matches.
+    pub fn new(
+        component: FetchData,
+        path: &str,
+        op: CompareOp,
+        value: Box<dyn Reflect>,
+    ) -> Result<Self, PredicateError> {
+        let parsed = ParsedPath::parse(path).map_err(|e| PredicateError::Path(e.to_string()))?;
+        let FetchData { id, from_ptr } = component;
+        Ok(Predicate {
+            id,
+            from_ptr,
+            path: parsed,
+            op,
+            value,
+            literal: None,
+        })
+    }
+    /// Same as [`Self::new`], parsing `literal` as RON into the field's type.
+    ///
+    /// The field's type is found from the [`TypeInfo`] of the component and
+    /// of the types along `path`, they must be registered in `registry`. The
+    /// field must be registered with `ReflectDeserialize`.
+    ///
+    /// In enums, `path` can point to a field of any variant.
+    #[cfg(feature = "parse")]
+    pub fn from_literal(
+        registry: &TypeRegistry,
+        component: FetchData,
+        path: &str,
+        op: CompareOp,
+        literal: &str,
+    ) -> Result<Self, PredicateError> {
+        let parsed = ParsedPath::parse(path).map_err(|e| PredicateError::Path(e.to_string()))?;
+        let type_id = component.from_ptr.type_id();
+        let Some(registration) = registry.get(type_id) else {
+            return Err(PredicateError::Unregistered(component.id));
+        };
+        let field = field_type_id(registry, type_id, &parsed.to_string());
+        let no_field = || {
+            let msg = format!("'{path}' is not a field of {}", registration.type_name());
+            PredicateError::Path(msg)
+        };
+        let field = field.ok_or_else(no_field)?;
+        let deserialize = registry.get_type_data::<ReflectDeserialize>(field);
+        let deserialize =
+            deserialize.ok_or_else(|| PredicateError::NotDeserializable(path.to_owned()))?;
+
+        let literal_error = |e: ron::Error| PredicateError::Literal(e.to_string());
+        let deserializer = ron::Deserializer::from_str(literal);
+        let mut deserializer = deserializer.map_err(|e| PredicateError::Literal(e.to_string()))?;
+        let value = deserialize.deserialize(&mut deserializer);
+        let value = value.map_err(literal_error)?;
+        deserializer.end().map_err(literal_error)?;
+
+        let FetchData { id, from_ptr } = component;
+        let literal = Some(literal.into());
+        Ok(Predicate { id, from_ptr, path: parsed, op, value, literal })
+    }
+    pub fn id(&self) -> ComponentId {
+        self.id
+    }
+    pub fn path(&self) -> &ParsedPath {
+        &self.path
+    }
+    pub fn op(&self) -> CompareOp {
+        self.op
+    }
+    pub fn value(&self) -> &dyn Reflect {
+        self.value.as_ref()
+    }
+    /// The text this predicate's value was parsed from, if created with
+    /// [`Self::from_literal`].
+    pub fn literal(&self) -> Option<&str> {
+        self.literal.as_deref()
+    }
+    /// Whether the component value `component` satisfies this predicate.
+    pub fn matches(&self, component: &dyn Reflect) -> bool {
+        let Ok(field) = self.path.reflect_element(component) else {
+            return false;
+        };
+        self.op.compare(field, self.value.as_ref())
+    }
+    /// Whether `entity` satisfies this predicate.
+    ///
+    /// # Safety
+    /// `entity` must allow reading the component of this predicate.
+    pub(crate) unsafe fn matches_entity(&self, entity: UnsafeEntityCell) -> bool {
+        let Some(ptr) = entity.get_by_id(self.id) else {
+            return false;
+        };
+        self.matches(self.from_ptr.as_reflect_ptr(ptr))
+    }
+    pub(crate) fn with_id(&self, id: ComponentId) -> Self {
+        Predicate { id, ..self.clone() }
+    }
+    /// What predicates are hashed by, everything but the value.
+    pub(crate) fn sort_key(&self) -> (ComponentId, &ParsedPath, CompareOp) {
+        (self.id, &self.path, self.op)
+    }
+    /// Order of predicates in canonical [`crate::DynamicQuery`].
+    ///
+    /// Predicates that only differ by their value are ordered by value when
+    /// it is a number, `bool`, `char` or `String`. Other values are considered
+    /// equal here, so they keep the order they were added in, and queries
+    /// with such predicates in a different order are not equal.
+    pub(crate) fn canonical_cmp(&self, other: &Self) -> Ordering {
+        let by_value = || canonical_value_cmp(self.value.as_ref(), other.value.as_ref());
+        self.sort_key().cmp(&other.sort_key()).then_with(by_value)
+    }
+}
+impl Clone for Predicate {
+    fn clone(&self) -> Self {
+        Predicate {
+            id: self.id,
+            from_ptr: self.from_ptr.clone(),
+            path: self.path.clone(),
+            op: self.op,
+            value: self.value.clone_value(),
+            literal: self.literal.clone(),
+        }
+    }
+}
+impl PartialEq for Predicate {
+    fn eq(&self, other: &Self) -> bool {
+        self.sort_key() == other.sort_key()
+            && self.value.reflect_partial_eq(other.value.as_ref()) == Some(true)
+    }
+}
+impl Eq for Predicate {}
+impl Hash for Predicate {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.sort_key().hash(state);
+    }
+}
+impl fmt::Debug for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Predicate")
+            .field("id", &self.id)
+            .field("path", &self.path)
+            .field("op", &self.op)
+            .field("value", &self.value)
+            .finish()
+    }
+}
+/// Displays as `path op value`, without the component name.
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = self.path.to_string();
+        let path = if path.is_empty() { "self" } else { &path };
+        match &self.literal {
+            Some(literal) => write!(f, "{path} {} {literal}", self.op),
+            None => write!(f, "{path} {} {:?}", self.op, self.value),
+        }
+    }
+}
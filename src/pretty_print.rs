@@ -1,11 +1,16 @@
-//! Nice formatting for `[DynamicItem]`
+//! Nice formatting for `[DynamicItem]` and [`DynamicQuery`].
 
 use std::{fmt, marker::PhantomData};
 
+use bevy_ecs::component::{ComponentId, Components};
 use bevy_ecs::{all_tuples, prelude::Entity};
 use bevy_reflect::Reflect;
+use bevy_utils::get_short_name;
 
-use crate::DynamicItem;
+use crate::builder::Fetch;
+use crate::filters::{Conjunction, Filter, FilterKind};
+use crate::predicate::Predicate;
+use crate::{DynamicItem, DynamicQuery};
 
 pub struct DynShow<'w, T: AsRef<[DynamicItem<'w>]>>(T, PhantomData<&'w ()>);
 pub struct DynShowT<'a, T: ShowReflect>(pub &'a T);
@@ -22,22 +27,22 @@ impl fmt::Debug for DynItem<'_, '_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self.0 {
             DynamicItem::Entity(e) => write!(f, "entity{e:?}"),
-            DynamicItem::Read(value) => {
+            DynamicItem::Read(_, value) => {
                 f.write_str("&")?;
                 value.debug(f)
             }
-            DynamicItem::Mut(value) => {
+            DynamicItem::Mut(_, value) => {
                 f.write_str("&mut ")?;
                 value.debug(f)
             }
-            DynamicItem::OptionRead(None) => f.write_str("None"),
-            DynamicItem::OptionMut(None) => f.write_str("None"),
-            DynamicItem::OptionRead(Some(value)) => {
+            DynamicItem::OptionRead(_, None) => f.write_str("None"),
+            DynamicItem::OptionMut(_, None) => f.write_str("None"),
+            DynamicItem::OptionRead(_, Some(value)) => {
                 f.write_str("Some(&")?;
                 value.debug(f)?;
                 f.write_str(")")
             }
-            DynamicItem::OptionMut(Some(value)) => {
+            DynamicItem::OptionMut(_, Some(value)) => {
                 f.write_str("Some(&mut ")?;
                 value.debug(f)?;
                 f.write_str(")")
@@ -49,7 +54,7 @@ impl fmt::Debug for DynItem<'_, '_> {
 impl<'w, T: AsRef<[DynamicItem<'w>]>> fmt::Debug for DynShow<'w, T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let items = self.0.as_ref();
-        if items.len() == 0 {
+        if items.is_empty() {
             return f.write_str("()");
         }
         if items.len() == 1 {
@@ -126,3 +131,168 @@ macro_rules! impl_show_reflect {
     };
 }
 all_tuples!(impl_show_reflect, 2, 13, T, t);
+
+/// Display a [`DynamicQuery`] with component names, see [`DynamicQuery::display`].
+pub struct DisplayQuery<'a> {
+    query: &'a DynamicQuery,
+    components: &'a Components,
+    compact: bool,
+}
+impl<'a> DisplayQuery<'a> {
+    pub(crate) fn new(query: &'a DynamicQuery, components: &'a Components) -> Self {
+        DisplayQuery { query, components, compact: false }
+    }
+    /// Use the compact console syntax rather than the rust-like one.
+    pub fn compact(mut self) -> Self {
+        self.compact = true;
+        self
+    }
+    fn name(&self, id: ComponentId) -> String {
+        let name = self.components.get_info(id).map(|info| info.name());
+        get_short_name(name.unwrap_or("<unregistered>"))
+    }
+    /// All fetches in the query, as they would appear in a rust `Query`.
+    fn rust_fetches(&self) -> Vec<String> {
+        let fetches = &self.query.fetches;
+        let mut ret = Vec::with_capacity(fetches.len());
+        let mut kind = |idx, prefix, suffix| {
+            let named = fetches.ids(idx).map(|id| self.name(id));
+            ret.extend(named.map(|name| format!("{prefix}{name}{suffix}")));
+        };
+        kind(Fetch::READ_IDX, "&", "");
+        kind(Fetch::MUT_IDX, "&mut ", "");
+        kind(Fetch::OPTION_READ_IDX, "Option<&", ">");
+        kind(Fetch::OPTION_MUT_IDX, "Option<&mut ", ">");
+        if fetches.has_entity {
+            ret.push("Entity".to_owned());
+        }
+        ret
+    }
+    fn compact_fetches(&self) -> Vec<String> {
+        let fetches = &self.query.fetches;
+        let mut ret = Vec::with_capacity(fetches.len());
+        let mut kind = |idx, prefix| {
+            let named = fetches.ids(idx).map(|id| self.name(id));
+            ret.extend(named.map(|name| format!("{prefix}{name}")));
+        };
+        kind(Fetch::READ_IDX, "");
+        kind(Fetch::MUT_IDX, "mut ");
+        kind(Fetch::OPTION_READ_IDX, "?");
+        kind(Fetch::OPTION_MUT_IDX, "?mut ");
+        if fetches.has_entity {
+            ret.push("Entity".to_owned());
+        }
+        ret
+    }
+    fn rust_filter(&self, filter: &Filter) -> String {
+        let name = self.name(filter.id());
+        match filter.kind() {
+            FilterKind::With => format!("With<{name}>"),
+            FilterKind::Without => format!("Without<{name}>"),
+            FilterKind::Changed => format!("Changed<{name}>"),
+            FilterKind::Added => format!("Added<{name}>"),
+        }
+    }
+    fn compact_filter(&self, filter: &Filter) -> String {
+        let name = self.name(filter.id());
+        match filter.kind() {
+            FilterKind::With => name,
+            FilterKind::Without => format!("!{name}"),
+            FilterKind::Changed => format!(">{name}"),
+            FilterKind::Added => format!("+{name}"),
+        }
+    }
+    /// `Component.path op literal`, with spaces around `op` if `spaced`.
+    ///
+    /// Predicates without a literal print the `Debug` of their value, which
+    /// may not parse back.
+    fn predicate(&self, predicate: &Predicate, spaced: bool) -> String {
+        let name = self.name(predicate.id());
+        let path = predicate.path().to_string();
+        let dot = if path.is_empty() || path.starts_with(['#', '[']) {
+            ""
+        } else {
+            "."
+        };
+        let op = predicate.op();
+        let op = if spaced { format!(" {op} ") } else { op.to_string() };
+        match predicate.literal() {
+            Some(literal) => format!("{name}{dot}{path}{op}{}", literal.trim()),
+            None => format!("{name}{dot}{path}{op}{:?}", predicate.value()),
+        }
+    }
+    /// The conjunctions to print. None if one of them is empty: it matches
+    /// every entity, and so do the filters.
+    fn conjunctions(&self) -> Vec<Conjunction<'_>> {
+        let conjunctions: Vec<_> = self.query.filters.conjunctions().collect();
+        let is_empty = |c: &Conjunction| {
+            c.filters.is_empty() && c.predicates.is_empty() && c.customs.is_empty()
+        };
+        if conjunctions.iter().any(is_empty) {
+            return Vec::new();
+        }
+        conjunctions
+    }
+    fn fmt_rust(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fetches = self.rust_fetches();
+        f.write_str("Query<")?;
+        fmt_tuple(f, &fetches)?;
+
+        let conjunctions = self.conjunctions();
+        let mut conjunctions = conjunctions.iter().map(|conjunction| {
+            let filters = conjunction.filters.iter().map(|f| self.rust_filter(f));
+            let predicates = conjunction.predicates.iter();
+            let predicates = predicates.map(|p| self.predicate(p, true));
+            let customs = conjunction.customs.iter().map(|c| format!("@{c:?}"));
+            filters.chain(predicates).chain(customs).collect::<Vec<_>>()
+        });
+        match conjunctions.len() {
+            0 => {}
+            1 => {
+                f.write_str(", ")?;
+                fmt_tuple(f, &conjunctions.next().unwrap())?;
+            }
+            _ => {
+                f.write_str(", Or<(")?;
+                for (i, conjunction) in conjunctions.enumerate() {
+                    f.write_str(if i == 0 { "" } else { ", " })?;
+                    fmt_tuple(f, &conjunction)?;
+                }
+                f.write_str(")>")?;
+            }
+        }
+        f.write_str(">")
+    }
+    fn fmt_compact(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fetches = self.compact_fetches();
+        // `()` marks an empty fetch list, which the parser would otherwise reject.
+        f.write_str(if fetches.is_empty() { "()" } else { "" })?;
+        f.write_str(&fetches.join(" "))?;
+        for (i, conjunction) in self.conjunctions().iter().enumerate() {
+            f.write_str(if i == 0 { ", " } else { " | " })?;
+            let filters = conjunction.filters.iter().map(|f| self.compact_filter(f));
+            let predicates = conjunction.predicates.iter();
+            let predicates = predicates.map(|p| self.predicate(p, false));
+            let customs = conjunction.customs.iter().map(|c| format!("@{c:?}"));
+            let filters: Vec<_> = filters.chain(predicates).chain(customs).collect();
+            f.write_str(&filters.join(" "))?;
+        }
+        Ok(())
+    }
+}
+/// Write `items` as a rust tuple, without parenthesis if there is a single item.
+fn fmt_tuple(f: &mut fmt::Formatter<'_>, items: &[String]) -> fmt::Result {
+    match items {
+        [single] => f.write_str(single),
+        items => write!(f, "({})", items.join(", ")),
+    }
+}
+impl fmt::Display for DisplayQuery<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.compact {
+            self.fmt_compact(f)
+        } else {
+            self.fmt_rust(f)
+        }
+    }
+}
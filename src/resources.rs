@@ -0,0 +1,190 @@
+//! Dynamic access to resources, the resource companion of [`DynamicQuery`].
+//!
+//! [`DynamicQuery`]: crate::DynamicQuery
+use std::collections::HashSet;
+
+use bevy_ecs::archetype::ArchetypeComponentId;
+use bevy_ecs::component::ComponentId;
+use bevy_ecs::query::{Access, FilteredAccess};
+use bevy_ecs::world::{unsafe_world_cell::UnsafeWorldCell, World};
+use bevy_reflect::ReflectFromPtr;
+
+use crate::builder::{Fetch, FetchData};
+use crate::maybe_item::{assume_init_mut, MaybeDynamicItem};
+use crate::{DynamicItem, DynamicQueryError};
+
+/// A runtime description of a set of resources to access.
+///
+/// Unlike with [`DynamicQuery`](crate::DynamicQuery), the items are
+/// returned in the same order as the fetches were declared.
+#[derive(Clone, Debug)]
+pub struct DynamicResources {
+    pub(crate) fetches: Box<[Fetch]>,
+}
+impl DynamicResources {
+    /// Returns `None` if `fetches` contains a [`Fetch::Entity`] or the same
+    /// resource twice.
+    pub fn new(fetches: Vec<Fetch>) -> Option<Self> {
+        // TODO(err): proper error reporting
+        if fetches.contains(&Fetch::Entity) {
+            return None;
+        }
+        let mut encountered = HashSet::with_capacity(fetches.len());
+        if fetches
+            .iter()
+            .any(|fetch| !encountered.insert(fetch.data().id))
+        {
+            return None;
+        }
+        Some(DynamicResources { fetches: fetches.into() })
+    }
+    pub fn is_empty(&self) -> bool {
+        self.fetches.is_empty()
+    }
+    pub fn state(&self, world: &World) -> DynamicResourcesState {
+        DynamicResourcesState::new(self, world)
+    }
+}
+
+/// The [`DynamicResources`] equivalent of [`DynamicState`](crate::DynamicState).
+#[derive(Clone, Debug)]
+pub struct DynamicResourcesState {
+    fetches: Box<[Fetch]>,
+    component_access: FilteredAccess<ComponentId>,
+    archetype_component_access: Access<ArchetypeComponentId>,
+    /// Fetches which `ArchetypeComponentId` is not yet known, because the
+    /// resource wasn't yet inserted in the world.
+    unregistered: Vec<usize>,
+    item_buffer: Box<[MaybeDynamicItem]>,
+}
+impl DynamicResourcesState {
+    pub fn new(resources: &DynamicResources, world: &World) -> Self {
+        let mut component_access = FilteredAccess::default();
+        for fetch in resources.fetches.iter() {
+            let id = fetch.data().id;
+            match fetch {
+                Fetch::Read(_) | Fetch::OptionRead(_) => component_access.access_mut().add_read(id),
+                Fetch::Mut(_) | Fetch::OptionMut(_) => component_access.access_mut().add_write(id),
+                Fetch::Entity => unreachable!("Checked in DynamicResources::new"),
+            }
+        }
+        let item_count = resources.fetches.len();
+        let mut state = DynamicResourcesState {
+            fetches: resources.fetches.clone(),
+            component_access,
+            archetype_component_access: Access::default(),
+            unregistered: (0..item_count).collect(),
+            item_buffer: vec![MaybeDynamicItem::uninit(); item_count].into(),
+        };
+        state.update_archetype_component_access(world.as_unsafe_world_cell_readonly());
+        state
+    }
+    /// Register the [`ArchetypeComponentId`] of resources inserted in `world`
+    /// since the last call.
+    pub fn update_archetype_component_access(&mut self, world: UnsafeWorldCell) {
+        // SAFETY: we only access world metadata
+        let resources = unsafe { &world.storages().resources };
+        self.unregistered.retain(|&i| {
+            let fetch = &self.fetches[i];
+            let Some(resource) = resources.get(fetch.data().id) else {
+                return true;
+            };
+            match fetch {
+                Fetch::Read(_) | Fetch::OptionRead(_) => {
+                    self.archetype_component_access.add_read(resource.id());
+                }
+                _ => self.archetype_component_access.add_write(resource.id()),
+            }
+            false
+        });
+    }
+    pub fn component_access(&self) -> &FilteredAccess<ComponentId> {
+        &self.component_access
+    }
+    pub fn archetype_component_access(&self) -> &Access<ArchetypeComponentId> {
+        &self.archetype_component_access
+    }
+
+    /// Get the resources.
+    ///
+    /// Like the component items of a [`DynamicState`](crate::DynamicState),
+    /// mutable items don't mark their resource as changed. Use
+    /// [`World::get_resource_mut_by_id`] to do so.
+    ///
+    /// # Errors
+    /// If a non-`Option` resource doesn't exist in `world`.
+    ///
+    /// # Safety
+    /// `world` must have mutable access to the resources mutably fetched by
+    /// this state, and read access to the rest.
+    /// No other items of this state must be alive for `'w`.
+    pub unsafe fn get_unchecked_manual<'w, 's>(
+        &'s mut self,
+        world: UnsafeWorldCell<'w>,
+    ) -> Result<&'s mut [DynamicItem<'w>], DynamicQueryError> {
+        let fetches = self.fetches.iter();
+        for (item, fetch) in self.item_buffer.iter_mut().zip(fetches) {
+            let FetchData { id, from_ptr } = fetch.data();
+            let missing = || DynamicQueryError::MissingResource(*id);
+            let read = |from_ptr: &ReflectFromPtr| {
+                let ptr = unsafe { world.get_resource_by_id(*id) };
+                ptr.map(|ptr| unsafe { from_ptr.as_reflect_ptr(ptr) })
+            };
+            let write = |from_ptr: &ReflectFromPtr| {
+                // Not `get_resource_mut_by_id`, which would mark the resource
+                // as changed even if it is never written to.
+                let ptr = unsafe { world.get_resource_by_id(*id) };
+                ptr.map(|ptr| unsafe { from_ptr.as_reflect_ptr_mut(ptr.assert_unique()) })
+            };
+            item.set(match fetch {
+                Fetch::Read(_) => DynamicItem::Read(*id, read(from_ptr).ok_or_else(missing)?),
+                Fetch::Mut(_) => DynamicItem::Mut(*id, write(from_ptr).ok_or_else(missing)?),
+                Fetch::OptionRead(_) => DynamicItem::OptionRead(*id, read(from_ptr)),
+                Fetch::OptionMut(_) => DynamicItem::OptionMut(*id, write(from_ptr)),
+                Fetch::Entity => unreachable!("Checked in DynamicResources::new"),
+            });
+        }
+        // SAFETY: we just initialized all buffer items
+        Ok(unsafe { assume_init_mut(self.item_buffer.as_mut()) })
+    }
+    /// Same as [`Self::get_unchecked_manual`], but only returns read-only items.
+    ///
+    /// # Safety
+    /// `world` must have read access to all the resources fetched by this state.
+    pub unsafe fn get_read_only_manual<'w, 's>(
+        &'s mut self,
+        world: UnsafeWorldCell<'w>,
+    ) -> Result<&'s [DynamicItem<'w>], DynamicQueryError> {
+        let fetches = self.fetches.iter();
+        for (item, fetch) in self.item_buffer.iter_mut().zip(fetches) {
+            let FetchData { id, from_ptr } = fetch.data();
+            let ptr = unsafe { world.get_resource_by_id(*id) };
+            let value = ptr.map(|ptr| unsafe { from_ptr.as_reflect_ptr(ptr) });
+            item.set(match fetch {
+                Fetch::Read(_) | Fetch::Mut(_) => {
+                    DynamicItem::Read(*id, value.ok_or(DynamicQueryError::MissingResource(*id))?)
+                }
+                Fetch::OptionRead(_) | Fetch::OptionMut(_) => DynamicItem::OptionRead(*id, value),
+                Fetch::Entity => unreachable!("Checked in DynamicResources::new"),
+            });
+        }
+        // SAFETY: we just initialized all buffer items
+        Ok(unsafe { assume_init_mut(self.item_buffer.as_mut()) })
+    }
+    pub fn get<'w, 's>(
+        &'s mut self,
+        world: &'w World,
+    ) -> Result<&'s [DynamicItem<'w>], DynamicQueryError> {
+        let world = world.as_unsafe_world_cell_readonly();
+        // SAFETY: we have read access to the whole world.
+        unsafe { self.get_read_only_manual(world) }
+    }
+    pub fn get_mut<'w, 's>(
+        &'s mut self,
+        world: &'w mut World,
+    ) -> Result<&'s mut [DynamicItem<'w>], DynamicQueryError> {
+        let world = world.as_unsafe_world_cell();
+        // SAFETY: we have exclusive access to the whole world.
+        unsafe { self.get_unchecked_manual(world) }
+    }
+}
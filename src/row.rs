@@ -0,0 +1,391 @@
+//! [`DynamicRow`], the items of a single entity returned by a [`DynamicState`].
+//!
+//! [`DynamicState`]: crate::DynamicState
+use std::any::{type_name, TypeId};
+use std::ops::{Deref, DerefMut};
+use std::sync::OnceLock;
+
+use bevy_ecs::component::{ComponentId, Components};
+use bevy_ecs::{all_tuples, prelude::Entity};
+use bevy_reflect::Reflect;
+use bevy_utils::get_short_name;
+use thiserror::Error;
+
+use crate::fetches::Fetches;
+use crate::{DynamicItem, DynamicState};
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum ExtractError {
+    #[error("The row has no `{0}` item.")]
+    Missing(String),
+    #[error("`{expected}` is fetched as `{found}`.")]
+    Mismatch {
+        expected: String,
+        found: &'static str,
+    },
+}
+
+/// The position in a row of each fetched component, sorted by key for
+/// binary search.
+type Names = Box<[(Box<str>, usize)]>;
+
+#[doc(hidden)]
+#[derive(Clone, Debug, Default)]
+pub struct RowIndex {
+    by_id: Box<[(ComponentId, usize)]>,
+    by_type: Box<[(TypeId, usize)]>,
+    /// Full and short names, initialized when fetching the first row, since
+    /// names are only known to the world.
+    by_name: OnceLock<Names>,
+}
+impl RowIndex {
+    pub(crate) fn new(fetches: &Fetches) -> Self {
+        let offset = fetches.has_entity as usize;
+        let items = || (offset..).zip(fetches.components());
+        let mut by_id: Box<[_]> = items().map(|(i, comp)| (comp.id, i)).collect();
+        let mut by_type: Box<[_]> = items()
+            .map(|(i, comp)| (comp.from_ptr.type_id(), i))
+            .collect();
+        by_id.sort_unstable();
+        by_type.sort_unstable();
+        RowIndex { by_id, by_type, by_name: OnceLock::new() }
+    }
+    pub(crate) fn init_names(&self, components: &Components) {
+        self.by_name.get_or_init(|| {
+            let full_names = self.by_id.iter().filter_map(|&(id, i)| {
+                Some((Box::<str>::from(components.get_info(id)?.name()), i))
+            });
+            let mut names: Vec<(Box<str>, usize)> = full_names.collect();
+            let short_names: Vec<(Box<str>, usize)> = names
+                .iter()
+                .map(|(name, i)| (get_short_name(name).into(), *i))
+                .collect();
+            // A short name shared with another component is ambiguous, those
+            // components can only be found by full name.
+            let ambiguous = |(short, i): &&(Box<str>, usize)| {
+                let other = |(name, j): &(Box<str>, usize)| name == short && j != i;
+                short_names.iter().any(other) || names.iter().any(other)
+            };
+            let short_names = short_names.iter().filter(|s| !ambiguous(s)).cloned();
+            let short_names: Vec<_> = short_names.collect();
+            names.extend(short_names);
+            // Remove short names equal to their full name.
+            names.sort();
+            names.dedup();
+            names.into()
+        });
+    }
+    pub(crate) fn by_id(&self, id: ComponentId) -> Option<usize> {
+        let index = self.by_id.binary_search_by_key(&id, |e| e.0).ok()?;
+        Some(self.by_id[index].1)
+    }
+    pub(crate) fn by_type(&self, id: TypeId) -> Option<usize> {
+        let index = self.by_type.binary_search_by_key(&id, |e| e.0).ok()?;
+        Some(self.by_type[index].1)
+    }
+    pub(crate) fn by_name(&self, name: &str) -> Option<usize> {
+        let names = self.by_name.get()?;
+        let index = names.binary_search_by_key(&name, |e| &e.0).ok()?;
+        Some(names[index].1)
+    }
+}
+
+#[derive(Debug)]
+enum Items<'w, 's> {
+    Owned(Box<[DynamicItem<'w>]>),
+    Borrowed(&'s mut [DynamicItem<'w>]),
+}
+
+/// The items of an entity matched by a [`DynamicState`].
+///
+/// Items are in a canonical order, independent of the order fetches were
+/// added to the [`DynamicQuery`]: the entity first, if fetched, then the
+/// `Read`, `Mut`, `OptionRead` and `OptionMut` components. Components of the
+/// same kind are sorted by [`ComponentId`].
+///
+/// A `DynamicRow` dereferences to a slice of [`DynamicItem`].
+/// Use [`Self::get`] and [`Self::get_by`] to access a single component
+/// without knowing its position, or [`Self::extract`] to get several items as
+/// rust types.
+///
+/// [`DynamicState`]: crate::DynamicState
+/// [`DynamicQuery`]: crate::DynamicQuery
+#[derive(Debug)]
+pub struct DynamicRow<'w, 's> {
+    items: Items<'w, 's>,
+    fetches: &'s Fetches,
+    index: &'s RowIndex,
+}
+impl<'w, 's> DynamicRow<'w, 's> {
+    pub(crate) fn new(items: Box<[DynamicItem<'w>]>, state: &'s DynamicState) -> Self {
+        let (fetches, index) = (&state.fetches, &state.row_index);
+        DynamicRow { items: Items::Owned(items), fetches, index }
+    }
+    pub(crate) fn borrowed(
+        items: &'s mut [DynamicItem<'w>],
+        fetches: &'s Fetches,
+        index: &'s RowIndex,
+    ) -> Self {
+        DynamicRow { items: Items::Borrowed(items), fetches, index }
+    }
+    /// The position in this row of the component at `key`, `None` if it is
+    /// not fetched.
+    pub fn index_of(&self, key: impl RowKey) -> Option<usize> {
+        key.index_in(self.index)
+    }
+    /// The component `T`, `None` if it is not fetched or the entity doesn't
+    /// have it.
+    pub fn get<T: Reflect>(&self) -> Option<&T> {
+        self.get_by(TypeId::of::<T>())?.downcast_ref()
+    }
+    /// The component `T`, `None` if it is not mutably fetched or the entity
+    /// doesn't have it.
+    pub fn get_mut<T: Reflect>(&mut self) -> Option<&mut T> {
+        self.get_mut_by(TypeId::of::<T>())?.downcast_mut()
+    }
+    /// The component at `key`, a [`ComponentId`], [`TypeId`], or the full or
+    /// short name of the component's type.
+    ///
+    /// Short names shared by several fetched components are ambiguous, use
+    /// the full name of those components.
+    ///
+    /// `None` if it is not fetched or the entity doesn't have it.
+    pub fn get_by(&self, key: impl RowKey) -> Option<&dyn Reflect> {
+        self[self.index_of(key)?].as_reflect()
+    }
+    /// Same as [`Self::get_by`], but `None` if the component is not mutably
+    /// fetched.
+    pub fn get_mut_by(&mut self, key: impl RowKey) -> Option<&mut dyn Reflect> {
+        let index = self.index_of(key)?;
+        self[index].as_reflect_mut()
+    }
+    /// The [`ComponentId`] of the `index`th item, `None` for the entity item.
+    pub fn component_id(&self, index: usize) -> Option<ComponentId> {
+        self.fetches.item_component(index).map(|comp| comp.id)
+    }
+    /// Get items of this row as rust types, such as
+    /// `row.extract::<(Entity, &A, &mut B, Option<&C>)>()`.
+    ///
+    /// Each element of the tuple is matched to the row item of the same
+    /// component and fetch kind, regardless of order:
+    ///
+    /// - `Entity` for [`DynamicItem::Entity`]
+    /// - `&T` for [`DynamicItem::Read`] or [`DynamicItem::Mut`]
+    /// - `&mut T` for [`DynamicItem::Mut`]
+    /// - `Option<&T>` for [`DynamicItem::OptionRead`] or [`DynamicItem::OptionMut`]
+    /// - `Option<&mut T>` for [`DynamicItem::OptionMut`]
+    ///
+    /// The tuple doesn't need to contain all the row's items.
+    ///
+    /// # Errors
+    /// If an element of the tuple has no corresponding item.
+    pub fn extract<'a, T: Extract<'a, 'w>>(&'a mut self) -> Result<T, ExtractError> {
+        let fetches = self.fetches;
+        let slots = self.iter_mut().enumerate().map(|(i, item)| {
+            let type_id = fetches.item_component(i).map(|c| c.from_ptr.type_id());
+            (type_id, Some(item))
+        });
+        T::extract(&mut RowSlots(slots.collect()))
+    }
+}
+impl<'w> Deref for DynamicRow<'w, '_> {
+    type Target = [DynamicItem<'w>];
+
+    fn deref(&self) -> &Self::Target {
+        match &self.items {
+            Items::Owned(items) => items,
+            Items::Borrowed(items) => items,
+        }
+    }
+}
+impl DerefMut for DynamicRow<'_, '_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match &mut self.items {
+            Items::Owned(items) => items,
+            Items::Borrowed(items) => items,
+        }
+    }
+}
+impl<'w> AsRef<[DynamicItem<'w>]> for DynamicRow<'w, '_> {
+    fn as_ref(&self) -> &[DynamicItem<'w>] {
+        self
+    }
+}
+impl<'w> AsMut<[DynamicItem<'w>]> for DynamicRow<'w, '_> {
+    fn as_mut(&mut self) -> &mut [DynamicItem<'w>] {
+        self
+    }
+}
+
+/// A way to identify a component in a [`DynamicRow`], see [`DynamicRow::get_by`].
+pub trait RowKey {
+    #[doc(hidden)]
+    fn index_in(&self, index: &RowIndex) -> Option<usize>;
+}
+impl RowKey for ComponentId {
+    fn index_in(&self, index: &RowIndex) -> Option<usize> {
+        index.by_id(*self)
+    }
+}
+impl RowKey for TypeId {
+    fn index_in(&self, index: &RowIndex) -> Option<usize> {
+        index.by_type(*self)
+    }
+}
+impl RowKey for &'_ str {
+    fn index_in(&self, index: &RowIndex) -> Option<usize> {
+        index.by_name(self)
+    }
+}
+
+fn kind_name(item: &DynamicItem) -> &'static str {
+    match item {
+        DynamicItem::Entity(_) => "Entity",
+        DynamicItem::Read(..) => "&T",
+        DynamicItem::Mut(..) => "&mut T",
+        DynamicItem::OptionRead(..) => "Option<&T>",
+        DynamicItem::OptionMut(..) => "Option<&mut T>",
+    }
+}
+
+/// The items of a row not yet extracted, with the type of their component.
+pub struct RowSlots<'a, 'w>(Vec<(Option<TypeId>, Option<&'a mut DynamicItem<'w>>)>);
+impl<'a, 'w> RowSlots<'a, 'w> {
+    fn take<T: ExtractItem<'a, 'w>>(&mut self) -> Result<T, ExtractError> {
+        let same_type = |(type_id, _): &&mut (Option<TypeId>, _)| *type_id == T::type_id();
+        let mut slots = self.0.iter_mut().filter(same_type);
+        let mut found = None;
+        let slot = slots.find_map(|(_, slot)| match slot {
+            Some(item) if T::matches_kind(item) => slot.take(),
+            Some(item) => {
+                found = Some(kind_name(item));
+                None
+            }
+            None => None,
+        });
+        let expected = T::describe;
+        let mismatch = |found| ExtractError::Mismatch { expected: expected(), found };
+        match (slot.and_then(T::extract_item), found) {
+            (Some(value), _) => Ok(value),
+            (None, Some(found)) => Err(mismatch(found)),
+            (None, None) => Err(ExtractError::Missing(expected())),
+        }
+    }
+}
+
+/// A tuple of [`ExtractItem`], see [`DynamicRow::extract`].
+pub trait Extract<'a, 'w>: Sized {
+    fn extract(slots: &mut RowSlots<'a, 'w>) -> Result<Self, ExtractError>;
+}
+/// A rust type a [`DynamicItem`] can be extracted as, see [`DynamicRow::extract`].
+pub trait ExtractItem<'a, 'w>: Sized {
+    /// The component type, `None` for `Entity`.
+    fn type_id() -> Option<TypeId>;
+    /// Whether `item` is of the right fetch kind, its type is checked separately.
+    fn matches_kind(item: &DynamicItem) -> bool;
+    fn extract_item(item: &'a mut DynamicItem<'w>) -> Option<Self>;
+    /// The name of this type, for error messages.
+    fn describe() -> String;
+}
+impl<'a, 'w> ExtractItem<'a, 'w> for Entity {
+    fn type_id() -> Option<TypeId> {
+        None
+    }
+    fn matches_kind(item: &DynamicItem) -> bool {
+        matches!(item, DynamicItem::Entity(_))
+    }
+    fn extract_item(item: &'a mut DynamicItem<'w>) -> Option<Self> {
+        match item {
+            DynamicItem::Entity(entity) => Some(*entity),
+            _ => None,
+        }
+    }
+    fn describe() -> String {
+        "Entity".to_owned()
+    }
+}
+impl<'a, 'w: 'a, T: Reflect> ExtractItem<'a, 'w> for &'a T {
+    fn type_id() -> Option<TypeId> {
+        Some(TypeId::of::<T>())
+    }
+    fn matches_kind(item: &DynamicItem) -> bool {
+        matches!(item, DynamicItem::Read(..) | DynamicItem::Mut(..))
+    }
+    fn extract_item(item: &'a mut DynamicItem<'w>) -> Option<Self> {
+        match item {
+            DynamicItem::Read(_, value) => value.downcast_ref(),
+            DynamicItem::Mut(_, value) => value.downcast_ref(),
+            _ => None,
+        }
+    }
+    fn describe() -> String {
+        format!("&{}", type_name::<T>())
+    }
+}
+impl<'a, 'w: 'a, T: Reflect> ExtractItem<'a, 'w> for &'a mut T {
+    fn type_id() -> Option<TypeId> {
+        Some(TypeId::of::<T>())
+    }
+    fn matches_kind(item: &DynamicItem) -> bool {
+        matches!(item, DynamicItem::Mut(..))
+    }
+    fn extract_item(item: &'a mut DynamicItem<'w>) -> Option<Self> {
+        match item {
+            DynamicItem::Mut(_, value) => value.downcast_mut(),
+            _ => None,
+        }
+    }
+    fn describe() -> String {
+        format!("&mut {}", type_name::<T>())
+    }
+}
+impl<'a, 'w: 'a, T: Reflect> ExtractItem<'a, 'w> for Option<&'a T> {
+    fn type_id() -> Option<TypeId> {
+        Some(TypeId::of::<T>())
+    }
+    fn matches_kind(item: &DynamicItem) -> bool {
+        matches!(
+            item,
+            DynamicItem::OptionRead(..) | DynamicItem::OptionMut(..)
+        )
+    }
+    fn extract_item(item: &'a mut DynamicItem<'w>) -> Option<Self> {
+        match item {
+            DynamicItem::OptionRead(_, None) | DynamicItem::OptionMut(_, None) => Some(None),
+            DynamicItem::OptionRead(_, Some(value)) => value.downcast_ref().map(Some),
+            DynamicItem::OptionMut(_, Some(value)) => value.downcast_ref().map(Some),
+            _ => None,
+        }
+    }
+    fn describe() -> String {
+        format!("Option<&{}>", type_name::<T>())
+    }
+}
+impl<'a, 'w: 'a, T: Reflect> ExtractItem<'a, 'w> for Option<&'a mut T> {
+    fn type_id() -> Option<TypeId> {
+        Some(TypeId::of::<T>())
+    }
+    fn matches_kind(item: &DynamicItem) -> bool {
+        matches!(item, DynamicItem::OptionMut(..))
+    }
+    fn extract_item(item: &'a mut DynamicItem<'w>) -> Option<Self> {
+        match item {
+            DynamicItem::OptionMut(_, None) => Some(None),
+            DynamicItem::OptionMut(_, Some(value)) => value.downcast_mut().map(Some),
+            _ => None,
+        }
+    }
+    fn describe() -> String {
+        format!("Option<&mut {}>", type_name::<T>())
+    }
+}
+macro_rules! impl_extract {
+    ($($T:ident),*) => {
+        impl<'a, 'w, $($T: ExtractItem<'a, 'w>),*> Extract<'a, 'w> for ($($T,)*) {
+            fn extract(slots: &mut RowSlots<'a, 'w>) -> Result<Self, ExtractError> {
+                Ok(($(slots.take::<$T>()?,)*))
+            }
+        }
+    };
+}
+all_tuples!(impl_extract, 1, 15, T);
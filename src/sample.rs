@@ -0,0 +1,101 @@
+//! Pick random entities matching a [`DynamicState`].
+use std::collections::BTreeSet;
+
+use bevy_ecs::world::World;
+use rand_core::RngCore;
+
+use crate::iter::{fetch_buffer_ro, MatchedEntities};
+use crate::{DynamicRow, DynamicState, Ticks};
+
+/// A uniformly distributed integer in `0..bound`.
+fn below(rng: &mut (impl RngCore + ?Sized), bound: usize) -> usize {
+    let bound = bound as u64;
+    // Reject the values that would make lower numbers more likely.
+    let threshold = bound.wrapping_neg() % bound;
+    loop {
+        let value = rng.next_u64();
+        if value >= threshold {
+            return (value % bound) as usize;
+        }
+    }
+}
+
+impl DynamicState {
+    /// The items of `n` distinct entities matching this state, chosen
+    /// uniformly at random, in no particular order.
+    ///
+    /// Returns all matched entities if less than `n` match.
+    ///
+    /// # Performance
+    ///
+    /// When no entity-level filters (`Added`, `Changed`, predicates or custom
+    /// entity filters) are involved, this only reads the length of matched
+    /// archetypes and the `n` chosen entities. Otherwise, all matched entities
+    /// are visited once, but only `n` are fetched.
+    pub fn sample<'w, 's>(
+        &'s mut self,
+        world: &'w World,
+        n: usize,
+        rng: &mut (impl RngCore + ?Sized),
+    ) -> Vec<DynamicRow<'w, 's>> {
+        if n == 0 {
+            return Vec::new();
+        }
+        let world = world.as_unsafe_world_cell_readonly();
+        let sampled = if self.archetype_ids.has_entity_checks() {
+            let ticks = Ticks::of_world(world);
+            // SAFETY: we have read access to the whole world.
+            let mut entities = unsafe { MatchedEntities::new(world, self, ticks) };
+            // Reservoir sampling: the `i`th entity replaces one of the sampled
+            // entities with probability `n / i`.
+            let mut sampled: Vec<_> = entities.by_ref().take(n).collect();
+            for (i, entity) in entities.enumerate() {
+                let j = below(rng, n + i + 1);
+                if j < n {
+                    sampled[j] = entity;
+                }
+            }
+            sampled
+        } else {
+            let archetypes = world.archetypes();
+            let matched = self.archetype_ids.iter(&self.filters);
+            let archetypes = matched.filter_map(|(id, _)| archetypes.get(id));
+            let archetypes: Vec<_> = archetypes.filter(|a| !a.is_empty()).collect();
+            let total: usize = archetypes.iter().map(|a| a.len()).sum();
+
+            // Floyd's algorithm, to select `n` distinct indices in `0..total`.
+            let mut indices = BTreeSet::new();
+            for i in total.saturating_sub(n)..total {
+                let j = below(rng, i + 1);
+                if !indices.insert(j) {
+                    indices.insert(i);
+                }
+            }
+            let (mut archetypes, mut offset) = (archetypes.into_iter(), 0);
+            let mut archetype = archetypes.next();
+            let mut sampled = Vec::with_capacity(indices.len());
+            for index in indices {
+                while let Some(current) = archetype.filter(|a| index >= offset + a.len()) {
+                    offset += current.len();
+                    archetype = archetypes.next();
+                }
+                let Some(current) = archetype else { break };
+                let entity = current.entities()[index - offset].entity();
+                sampled.extend(world.get_entity(entity));
+            }
+            sampled
+        };
+        let fetch = |entity| fetch_buffer_ro(self, entity);
+        sampled.into_iter().map(fetch).collect()
+    }
+    /// The items of an entity matching this state, chosen uniformly at random.
+    ///
+    /// See [`Self::sample`].
+    pub fn choose<'w, 's>(
+        &'s mut self,
+        world: &'w World,
+        rng: &mut (impl RngCore + ?Sized),
+    ) -> Option<DynamicRow<'w, 's>> {
+        self.sample(world, 1, rng).pop()
+    }
+}
@@ -0,0 +1,130 @@
+//! Copy the components fetched by a [`DynamicState`] out of the world, and
+//! write them back later.
+//!
+//! See [`DynamicState::snapshot`] and [`DynamicState::apply_snapshot`].
+use bevy_ecs::component::ComponentId;
+use bevy_ecs::prelude::Entity;
+use bevy_ecs::reflect::AppTypeRegistry;
+use bevy_ecs::world::World;
+use bevy_reflect::{Reflect, ReflectFromReflect, TypeInfo};
+
+use crate::builder::Fetch;
+use crate::iter::MatchedEntities;
+use crate::{DynamicState, Ticks};
+
+/// The value of a component, cloned out of the world.
+#[derive(Debug)]
+pub struct SnapshotValue {
+    pub id: ComponentId,
+    pub value: Box<dyn Reflect>,
+}
+
+/// The components of an entity, cloned out of the world.
+#[derive(Debug)]
+pub struct SnapshotRow {
+    pub entity: Entity,
+    /// The fetched components of `entity`, in the order of their items in a
+    /// [`crate::DynamicRow`]. Absent optional components are skipped.
+    pub values: Vec<SnapshotValue>,
+}
+
+/// The rows of a [`DynamicState`], owned and detached from the world, see
+/// [`DynamicState::snapshot`].
+#[derive(Debug, Default)]
+pub struct Snapshot {
+    pub rows: Vec<SnapshotRow>,
+}
+
+impl DynamicState {
+    /// Clone the components of all entities matching this state.
+    ///
+    /// Values are cloned with [`ReflectFromReflect`] when the world's
+    /// [`AppTypeRegistry`] has it, so that they can be downcast to their
+    /// concrete type. Otherwise, they are dynamic types from
+    /// [`Reflect::clone_value`].
+    ///
+    /// Rows are in the same order as [`Self::iter`].
+    pub fn snapshot(&mut self, world: &World) -> Snapshot {
+        self.update_order(world);
+
+        let registry = world.get_resource::<AppTypeRegistry>();
+        let registry = registry.map(|registry| registry.read());
+        let from_reflects: Vec<_> = self
+            .fetches
+            .components()
+            .iter()
+            .map(|comp| {
+                let registry = registry.as_ref()?;
+                registry.get_type_data::<ReflectFromReflect>(comp.from_ptr.type_id())
+            })
+            .collect();
+
+        let world = world.as_unsafe_world_cell_readonly();
+        let ticks = Ticks::of_world(world);
+        // SAFETY: we have read access to the whole world.
+        let entities = unsafe { MatchedEntities::new(world, self, ticks) };
+        let rows = entities.map(|entity| {
+            let comps = self.fetches.components().iter().zip(&from_reflects);
+            let values = comps.filter_map(|(comp, from_reflect)| {
+                // SAFETY: we have read access to the whole world, and
+                // `from_ptr` is the `ReflectFromPtr` of the component `id`.
+                let value = unsafe { comp.from_ptr.as_reflect_ptr(entity.get_by_id(comp.id)?) };
+                let cloned = from_reflect.and_then(|f| f.from_reflect(value));
+                let value = cloned.unwrap_or_else(|| value.clone_value());
+                Some(SnapshotValue { id: comp.id, value })
+            });
+            SnapshotRow { entity: entity.id(), values: values.collect() }
+        });
+        Snapshot { rows: rows.collect() }
+    }
+    /// Write back the values of `snapshot` into `world`, marking them as
+    /// changed.
+    ///
+    /// Only components fetched mutably by this state are written. Rows of
+    /// entities that no longer match this state, values of components the
+    /// entity lost, and values of a different type than their component
+    /// are skipped.
+    ///
+    /// Entities are matched with the same filters as [`Self::iter`], using
+    /// the world's current ticks. So with an `Added` or `Changed` filter,
+    /// the rows of entities that weren't added or changed since the
+    /// world's last change tick are skipped, even if they were in the
+    /// snapshot.
+    ///
+    /// Returns the number of rows where at least one value was written.
+    pub fn apply_snapshot(&self, world: &mut World, snapshot: &Snapshot) -> usize {
+        let world = world.as_unsafe_world_cell();
+        let ticks = Ticks::of_world(world);
+        let is_mutable = |id: ComponentId| {
+            let mut mutable = self.fetches.ids(Fetch::MUT_IDX);
+            let mut option_mutable = self.fetches.ids(Fetch::OPTION_MUT_IDX);
+            mutable.any(|i| i == id) || option_mutable.any(|i| i == id)
+        };
+        let mut written = 0;
+        for SnapshotRow { entity, values } in &snapshot.rows {
+            let Ok(entity) = self.matching_entity(world, *entity, ticks) else {
+                continue;
+            };
+            let mut row_written = false;
+            for SnapshotValue { id, value } in values {
+                let Some(from_ptr) = self.fetches.reflect_from_ptr(*id) else {
+                    continue;
+                };
+                let type_id = value.get_represented_type_info().map(TypeInfo::type_id);
+                if !is_mutable(*id) || type_id != Some(from_ptr.type_id()) {
+                    continue;
+                }
+                // SAFETY: we have exclusive access to the whole world.
+                let Some(component) = (unsafe { entity.get_mut_by_id(*id) }) else {
+                    continue;
+                };
+                // SAFETY: `from_ptr` is the `ReflectFromPtr` of the component `id`.
+                let component = unsafe { from_ptr.as_reflect_ptr_mut(component.into_inner()) };
+                component.apply(value.as_ref());
+                row_written = true;
+            }
+            written += usize::from(row_written);
+        }
+        written
+    }
+}
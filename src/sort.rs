@@ -0,0 +1,105 @@
+//! Iterate over a [`DynamicState`] sorted by the value of a reflected field.
+//!
+//! See [`DynamicState::iter_sorted_by`].
+use std::cmp::Ordering;
+
+use bevy_ecs::world::unsafe_world_cell::UnsafeEntityCell;
+use bevy_ecs::world::World;
+use bevy_reflect::Reflect;
+
+use crate::field::{FieldError, FieldPath};
+use crate::iter::{fetch_buffer_ro, MatchedEntities};
+use crate::predicate::reflect_partial_cmp;
+use crate::{DynamicRow, DynamicState, Ticks};
+
+/// In which order [`DynamicState::iter_sorted_by`] returns rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+/// The value an entity is sorted by.
+pub(crate) struct SortKey<'w> {
+    pub(crate) value: Option<&'w dyn Reflect>,
+    pub(crate) entity: UnsafeEntityCell<'w>,
+}
+impl SortKey<'_> {
+    /// Values that can't be ordered come after the others, then missing values.
+    fn rank(&self) -> u8 {
+        match self.value {
+            Some(value) if reflect_partial_cmp(value, value).is_some() => 0,
+            Some(_) => 1,
+            None => 2,
+        }
+    }
+    pub(crate) fn cmp(&self, other: &Self, order: SortOrder) -> Ordering {
+        let by_value = || match (self.value, other.value) {
+            (Some(value), Some(other)) if self.rank() == 0 => {
+                let ordering = reflect_partial_cmp(value, other).unwrap_or(Ordering::Equal);
+                match order {
+                    SortOrder::Ascending => ordering,
+                    SortOrder::Descending => ordering.reverse(),
+                }
+            }
+            _ => Ordering::Equal,
+        };
+        let by_entity = || self.entity.id().cmp(&other.entity.id());
+        self.rank()
+            .cmp(&other.rank())
+            .then_with(by_value)
+            .then_with(by_entity)
+    }
+}
+
+/// Rows of a [`DynamicState`] sorted with [`DynamicState::iter_sorted_by`].
+pub struct SortedDynamicQueryIter<'w, 's> {
+    entities: std::vec::IntoIter<UnsafeEntityCell<'w>>,
+    state: &'s DynamicState,
+}
+impl<'w: 's, 's> Iterator for SortedDynamicQueryIter<'w, 's> {
+    type Item = DynamicRow<'w, 's>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entity = self.entities.next()?;
+        Some(fetch_buffer_ro(self.state, entity))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entities.size_hint()
+    }
+}
+impl<'w: 's, 's> ExactSizeIterator for SortedDynamicQueryIter<'w, 's> {}
+
+impl DynamicState {
+    /// Iterate over the items of all entities matching this state, sorted by
+    /// the value at `path`.
+    ///
+    /// `path` is the name of a component fetched by this state, optionally
+    /// followed by a reflect path within it, such as `Health.current`.
+    ///
+    /// Numbers, `bool`, `char` and `String` are ordered. Other values come
+    /// after them, then entities without the component. Entities with equal
+    /// values are ordered by [`Entity`].
+    ///
+    /// This collects all matched entities before returning the first row.
+    pub fn iter_sorted_by<'w, 's>(
+        &'s mut self,
+        world: &'w World,
+        path: &str,
+        order: SortOrder,
+    ) -> Result<SortedDynamicQueryIter<'w, 's>, FieldError> {
+        let field = FieldPath::new(&self.fetches, world.components(), path)?;
+
+        let world = world.as_unsafe_world_cell_readonly();
+        // SAFETY: we have read access to the whole world.
+        let entities = unsafe { MatchedEntities::new(world, self, Ticks::of_world(world)) };
+        // SAFETY: we have read access to the whole world.
+        let keys = entities.map(|entity| SortKey { value: unsafe { field.get(entity) }, entity });
+        let mut keys: Vec<_> = keys.collect();
+        keys.sort_by(|l, r| l.cmp(r, order));
+
+        let entities: Vec<_> = keys.into_iter().map(|key| key.entity).collect();
+        Ok(SortedDynamicQueryIter { entities: entities.into_iter(), state: self })
+    }
+}
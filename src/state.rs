@@ -1,13 +1,18 @@
-use bevy_ecs::archetype::Archetypes;
+use bevy_ecs::archetype::{ArchetypeComponentId, Archetypes};
+use bevy_ecs::component::ComponentId;
+use bevy_ecs::query::{Access, FilteredAccess};
 use bevy_ecs::world::unsafe_world_cell::{UnsafeEntityCell, UnsafeWorldCell};
 use bevy_ecs::{component::Tick, prelude::Entity, world::World};
 use thiserror::Error;
 
+use crate::access::{add_archetype_access, component_access};
 use crate::archematch::MatchedArchetypes;
-use crate::dynamic_query::{DynamicItem, DynamicQuery};
+use crate::dynamic_query::DynamicQuery;
 use crate::iter::{DynamicQueryIter, RoDynamicQueryIter};
 use crate::maybe_item::{assume_init_mut, MaybeDynamicItem};
-use crate::{fetches::Fetches, filters::Filters};
+use crate::order::EntityOrder;
+use crate::row::{DynamicRow, RowIndex};
+use crate::{fetches::Fetches, filters::Filters, ArchetypeIndex};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Ticks {
@@ -23,6 +28,10 @@ impl Ticks {
     pub fn new(last_run: Tick, this_run: Tick) -> Self {
         Self { last_run, this_run }
     }
+    /// The ticks of `world`, as used when querying it outside of a system.
+    pub fn of_world(world: UnsafeWorldCell) -> Self {
+        Self::new(world.last_change_tick(), world.change_tick())
+    }
 }
 
 #[derive(Debug, Error)]
@@ -33,6 +42,8 @@ pub enum DynamicQueryError {
         "Entity with id {0:?} doesn't have the right set of components to satisfy DynamicState."
     )]
     Unmatched(Entity),
+    #[error("No resource with component id {0:?} exists in the world.")]
+    MissingResource(ComponentId),
 }
 
 #[derive(Clone, Debug)]
@@ -40,10 +51,13 @@ pub struct DynamicState {
     pub(crate) fetches: Fetches,
     pub(crate) filters: Filters,
     pub(crate) archetype_ids: MatchedArchetypes,
+    component_access: FilteredAccess<ComponentId>,
+    archetype_component_access: Access<ArchetypeComponentId>,
     item_buffer: Box<[MaybeDynamicItem]>,
+    pub(crate) order: Option<EntityOrder>,
+    pub(crate) row_index: RowIndex,
 }
 impl DynamicState {
-    // TODO(feat): Query caching
     pub fn in_world(query: &DynamicQuery, world: &mut World) -> Self {
         Self::new(query, world.archetypes())
     }
@@ -54,11 +68,62 @@ impl DynamicState {
         let mut state = DynamicState {
             fetches: query.fetches.clone(),
             filters: query.filters.clone(),
-            archetype_ids: MatchedArchetypes::new(&query.fetches, &query.filters, world),
+            archetype_ids: MatchedArchetypes::default(),
+            component_access: component_access(&query.fetches, &query.filters),
+            archetype_component_access: Access::default(),
+            item_buffer,
+            order: None,
+            row_index: RowIndex::new(&query.fetches),
+        };
+        state.add_archetypes(world);
+        state
+    }
+    /// Same as [`DynamicState::new`], but uses `index` to find matching archetypes.
+    ///
+    /// # Panics
+    /// If `index` is not up to date with `world`, see [`ArchetypeIndex::update`].
+    pub fn new_indexed(query: &DynamicQuery, world: &Archetypes, index: &ArchetypeIndex) -> Self {
+        let item_count = query.fetches.len();
+        let item_buffer = vec![MaybeDynamicItem::uninit(); item_count].into();
+
+        let mut state = DynamicState {
+            fetches: query.fetches.clone(),
+            filters: query.filters.clone(),
+            archetype_ids: MatchedArchetypes::default(),
+            component_access: component_access(&query.fetches, &query.filters),
+            archetype_component_access: Access::default(),
             item_buffer,
+            order: None,
+            row_index: RowIndex::new(&query.fetches),
         };
+        state.add_archetypes_indexed(world, index);
         state
     }
+    /// Same as [`DynamicState::add_archetypes`], but uses `index` to find
+    /// matching archetypes.
+    ///
+    /// # Panics
+    /// If `index` is not up to date with `archetypes`, see [`ArchetypeIndex::update`].
+    pub fn add_archetypes_indexed(&mut self, archetypes: &Archetypes, index: &ArchetypeIndex) {
+        assert_eq!(
+            index.len(),
+            archetypes.len(),
+            "ArchetypeIndex is not up to date with the world's archetypes"
+        );
+        let first_new = self.archetype_ids.last_gen();
+        if first_new == archetypes.len() {
+            return;
+        }
+        self.archetype_ids
+            .add_archetypes_indexed(&self.fetches, &self.filters, archetypes, index);
+
+        for archetype in archetypes.iter().skip(first_new) {
+            if self.archetype_ids.contains(archetype.id()) {
+                let access = &mut self.archetype_component_access;
+                add_archetype_access(&self.fetches, &self.filters, archetype, access);
+            }
+        }
+    }
     /// Verify if this `DynamicState` matches `archetype`, adding it to its internal list
     /// of archetypes and returns `true` if so.
     ///
@@ -69,13 +134,41 @@ impl DynamicState {
     /// This is `O(n * c)` where 'n' is the size of the archetype and 'c' is
     /// the number of filter conjunctions (ie: `Or` clauses).
     pub fn add_archetypes(&mut self, archetypes: &Archetypes) {
+        let first_new = self.archetype_ids.last_gen();
         self.archetype_ids
             .add_archetypes(&self.fetches, &self.filters, archetypes);
+
+        for archetype in archetypes.iter().skip(first_new) {
+            if self.archetype_ids.contains(archetype.id()) {
+                let access = &mut self.archetype_component_access;
+                add_archetype_access(&self.fetches, &self.filters, archetype, access);
+            }
+        }
+    }
+    /// The components this state reads and writes, and the `With`/`Without`
+    /// filters that may prove it disjoint from other queries.
+    pub fn component_access(&self) -> &FilteredAccess<ComponentId> {
+        &self.component_access
+    }
+    /// The [`ArchetypeComponentId`]s this state accesses in the archetypes
+    /// it matched so far.
+    ///
+    /// Call [`DynamicState::add_archetypes`] to update it.
+    pub fn archetype_component_access(&self) -> &Access<ArchetypeComponentId> {
+        &self.archetype_component_access
+    }
+    /// Whether this and `other` can safely access the world at the same time.
+    pub fn is_compatible(&self, other: &DynamicState) -> bool {
+        self.component_access.is_compatible(&other.component_access)
     }
 
     /// Overwrites `self.item_buffer` with the `fetch` items from provided
     /// table row and returns the buffer as-is.
-    fn buffer_row<'s, 'w>(&'s mut self, entity: UnsafeEntityCell<'w>) -> &'s mut [DynamicItem<'w>] {
+    ///
+    /// # Safety
+    /// You must have mut/read access to the mut/read components of `entity`
+    /// in this state's `Fetches`.
+    unsafe fn buffer_row<'s, 'w>(&'s mut self, entity: UnsafeEntityCell<'w>) -> DynamicRow<'w, 's> {
         // SAFETY: by construction item_buffer is same length as self.fetches
         unsafe { assert_invariant!(self.fetches.len() == self.item_buffer.len()) };
 
@@ -86,49 +179,125 @@ impl DynamicState {
             i.set(v);
         });
         // SAFETY: we just initialized all buffer items
-        unsafe { assume_init_mut(self.item_buffer.as_mut()) }
+        let items = unsafe { assume_init_mut(self.item_buffer.as_mut()) };
+        self.row_index.init_names(entity.world().components());
+        DynamicRow::borrowed(items, &self.fetches, &self.row_index)
     }
-
-    pub fn get_unchecked_manual<'w, 's>(
+    /// Same as [`Self::buffer_row`], but never returns mutable items.
+    ///
+    /// # Safety
+    /// You must have read access to the components of `entity` in this
+    /// state's `Fetches`.
+    unsafe fn buffer_row_read_only<'s, 'w>(
         &'s mut self,
+        entity: UnsafeEntityCell<'w>,
+    ) -> DynamicRow<'w, 's> {
+        // SAFETY: by construction item_buffer is same length as self.fetches
+        unsafe { assert_invariant!(self.fetches.len() == self.item_buffer.len()) };
+
+        let iter = unsafe { self.fetches.iter_read_only(entity) };
+        self.item_buffer.iter_mut().zip(iter).for_each(|(i, v)| {
+            i.set(v);
+        });
+        // SAFETY: we just initialized all buffer items
+        let items = unsafe { assume_init_mut(self.item_buffer.as_mut()) };
+        self.row_index.init_names(entity.world().components());
+        DynamicRow::borrowed(items, &self.fetches, &self.row_index)
+    }
+    pub(crate) fn matching_entity<'w>(
+        &self,
         world: UnsafeWorldCell<'w>,
         entity: Entity,
-    ) -> Result<&'s mut [DynamicItem<'w>], DynamicQueryError> {
-        let ticks = Ticks {
-            last_run: world.last_change_tick(),
-            this_run: world.change_tick(),
-        };
+        ticks: Ticks,
+    ) -> Result<UnsafeEntityCell<'w>, DynamicQueryError> {
         let dangling_entity = DynamicQueryError::Dangling(entity);
         let entity = world.get_entity(entity).ok_or(dangling_entity)?;
-        let archetype = entity.archetype();
         let getter = self.archetype_ids.getter(&self.filters);
         if !getter.contains(ticks, entity) {
             return Err(DynamicQueryError::Unmatched(entity.id()));
         }
-        Ok(self.buffer_row(entity))
+        Ok(entity)
+    }
+
+    /// Get the items of `entity`, using `ticks` for `Changed` and `Added` filters.
+    ///
+    /// # Safety
+    /// `world` must have mutable access to the components mutably fetched by
+    /// this state, and read access to the rest.
+    /// No other items of this state must be alive for `'w`.
+    pub unsafe fn get_unchecked_manual<'w, 's>(
+        &'s mut self,
+        world: UnsafeWorldCell<'w>,
+        entity: Entity,
+        ticks: Ticks,
+    ) -> Result<DynamicRow<'w, 's>, DynamicQueryError> {
+        let entity = self.matching_entity(world, entity, ticks)?;
+        Ok(unsafe { self.buffer_row(entity) })
+    }
+    /// Same as [`Self::get_unchecked_manual`], but only returns read-only items.
+    ///
+    /// # Safety
+    /// `world` must have read access to all the components fetched by this state.
+    pub unsafe fn get_read_only_manual<'w, 's>(
+        &'s mut self,
+        world: UnsafeWorldCell<'w>,
+        entity: Entity,
+        ticks: Ticks,
+    ) -> Result<DynamicRow<'w, 's>, DynamicQueryError> {
+        let entity = self.matching_entity(world, entity, ticks)?;
+        Ok(unsafe { self.buffer_row_read_only(entity) })
     }
     pub fn get<'w, 's>(
         &'s mut self,
         world: &'w World,
         entity: Entity,
-    ) -> Result<&'s [DynamicItem<'w>], DynamicQueryError> {
+    ) -> Result<DynamicRow<'w, 's>, DynamicQueryError> {
         let world = world.as_unsafe_world_cell_readonly();
-        self.get_unchecked_manual(world, entity).map(|x| &*x)
+        // SAFETY: we have read access to the whole world.
+        unsafe { self.get_read_only_manual(world, entity, Ticks::of_world(world)) }
     }
     pub fn get_mut<'w, 's>(
         &'s mut self,
         world: &'w mut World,
         entity: Entity,
-    ) -> Result<&'s mut [DynamicItem<'w>], DynamicQueryError> {
+    ) -> Result<DynamicRow<'w, 's>, DynamicQueryError> {
         let world = world.as_unsafe_world_cell();
-        self.get_unchecked_manual(world, entity)
+        // SAFETY: we have exclusive access to the whole world.
+        unsafe { self.get_unchecked_manual(world, entity, Ticks::of_world(world)) }
+    }
+    /// Iterate over the items of all entities matching this state.
+    ///
+    /// # Safety
+    /// `world` must have mutable access to the components mutably fetched by
+    /// this state, and read access to the rest.
+    pub unsafe fn iter_unchecked_manual<'w, 's>(
+        &'s self,
+        world: UnsafeWorldCell<'w>,
+        ticks: Ticks,
+    ) -> DynamicQueryIter<'w, 's> {
+        unsafe { DynamicQueryIter::new(world, self, ticks) }
+    }
+    /// Same as [`Self::iter_unchecked_manual`], but only returns read-only items.
+    ///
+    /// # Safety
+    /// `world` must have read access to all the components fetched by this state.
+    pub unsafe fn iter_read_only_manual<'w, 's>(
+        &'s self,
+        world: UnsafeWorldCell<'w>,
+        ticks: Ticks,
+    ) -> RoDynamicQueryIter<'w, 's> {
+        unsafe { RoDynamicQueryIter::new(world, self, ticks) }
     }
     pub fn iter<'w, 's>(&'s mut self, world: &'w World) -> RoDynamicQueryIter<'w, 's> {
+        self.update_order(world);
         let world = world.as_unsafe_world_cell_readonly();
-        RoDynamicQueryIter::new(world, self)
+        // SAFETY: we have read access to the whole world.
+        unsafe { self.iter_read_only_manual(world, Ticks::of_world(world)) }
     }
     pub fn iter_mut<'w, 's>(&'s mut self, world: &'w mut World) -> DynamicQueryIter<'w, 's> {
+        self.update_order(world);
         let world = world.as_unsafe_world_cell();
-        DynamicQueryIter::new(world, self)
+        // SAFETY: we have exclusive access to the whole world.
+        unsafe { self.iter_unchecked_manual(world, Ticks::of_world(world)) }
     }
 }
@@ -0,0 +1,186 @@
+//! [`DynamicSystem`], a bevy [`System`] running a closure over [`DynamicQuery`]s.
+use std::{any::TypeId, borrow::Cow};
+
+use bevy_ecs::archetype::ArchetypeComponentId;
+use bevy_ecs::component::{ComponentId, Tick};
+use bevy_ecs::query::{Access, FilteredAccessSet};
+use bevy_ecs::system::System;
+use bevy_ecs::world::{unsafe_world_cell::UnsafeWorldCell, World, WorldId};
+use tracing::warn;
+
+use crate::{DynamicItem, DynamicQuery, DynamicQueryParam, DynamicState, Ticks};
+use crate::{DynamicResources, DynamicResourcesState};
+
+type DynamicSystemFn = dyn FnMut(&mut [DynamicQueryParam], &mut [DynamicItem]) + Send + Sync;
+
+/// A [`System`] running `func` over a set of [`DynamicQuery`]s.
+///
+/// Each query is exposed to `func` as a [`DynamicQueryParam`], in the same
+/// order as they were passed to [`DynamicSystem::new`]. The second argument
+/// of `func` are the resources declared with [`DynamicSystem::with_resources`].
+///
+/// Since it declares the world access of its queries, the schedule can run
+/// it in parallel with non-conflicting systems.
+///
+/// # Panics
+///
+/// When initialized, if two of its queries have conflicting access.
+/// Use `With` and `Without` filters to make them disjoint.
+///
+/// When run, if one of its non-`Option` resources doesn't exist.
+pub struct DynamicSystem {
+    name: Cow<'static, str>,
+    queries: Vec<DynamicQuery>,
+    states: Vec<DynamicState>,
+    resources: DynamicResources,
+    resources_state: Option<DynamicResourcesState>,
+    func: Box<DynamicSystemFn>,
+    component_access: Access<ComponentId>,
+    archetype_component_access: Access<ArchetypeComponentId>,
+    last_run: Tick,
+    world_id: Option<WorldId>,
+}
+impl DynamicSystem {
+    pub fn new(
+        name: impl Into<Cow<'static, str>>,
+        queries: Vec<DynamicQuery>,
+        func: impl FnMut(&mut [DynamicQueryParam], &mut [DynamicItem]) + Send + Sync + 'static,
+    ) -> Self {
+        DynamicSystem {
+            name: name.into(),
+            queries,
+            states: Vec::new(),
+            resources: DynamicResources::new(Vec::new()).unwrap(),
+            resources_state: None,
+            func: Box::new(func),
+            component_access: Access::default(),
+            archetype_component_access: Access::default(),
+            last_run: Tick::new(0),
+            world_id: None,
+        }
+    }
+    /// Also access `resources` when running this system.
+    pub fn with_resources(mut self, resources: DynamicResources) -> Self {
+        self.resources = resources;
+        self
+    }
+    pub fn queries(&self) -> &[DynamicQuery] {
+        &self.queries
+    }
+    /// The states of this system's queries, empty if not yet initialized.
+    pub fn states(&self) -> &[DynamicState] {
+        &self.states
+    }
+}
+impl System for DynamicSystem {
+    type In = ();
+    type Out = ();
+
+    fn name(&self) -> Cow<'static, str> {
+        self.name.clone()
+    }
+    fn type_id(&self) -> TypeId {
+        TypeId::of::<Self>()
+    }
+    fn component_access(&self) -> &Access<ComponentId> {
+        &self.component_access
+    }
+    fn archetype_component_access(&self) -> &Access<ArchetypeComponentId> {
+        &self.archetype_component_access
+    }
+    fn is_send(&self) -> bool {
+        true
+    }
+    fn is_exclusive(&self) -> bool {
+        false
+    }
+    unsafe fn run_unsafe(&mut self, (): (), world: UnsafeWorldCell) {
+        let this_run = world.increment_change_tick();
+        let ticks = Ticks::new(self.last_run, this_run);
+
+        let states = self.states.iter_mut();
+        // SAFETY: `System::run_unsafe` invariants ensure we have access to what
+        // is declared in `archetype_component_access`, which is the union of
+        // all states accesses. `initialize` checks states do not conflict.
+        let params = states.map(|state| unsafe { DynamicQueryParam::new(world, state, ticks) });
+        let mut params: Vec<_> = params.collect();
+
+        let resources = self.resources_state.as_mut();
+        let resources = unsafe { resources.map(|r| r.get_unchecked_manual(world)) };
+        let resources = match resources {
+            Some(Ok(resources)) => resources,
+            Some(Err(err)) => panic!("Resource requested by {} does not exist: {err}", self.name),
+            None => &mut [],
+        };
+        (self.func)(&mut params, resources);
+
+        self.last_run = this_run;
+    }
+    fn apply_deferred(&mut self, _world: &mut World) {}
+
+    fn initialize(&mut self, world: &mut World) {
+        self.world_id = Some(world.id());
+        self.last_run = Tick::new(world.change_tick().get().wrapping_sub(Tick::MAX.get()));
+
+        let mut access_set = FilteredAccessSet::default();
+        self.states = Vec::with_capacity(self.queries.len());
+        for query in &self.queries {
+            let state = query.state(world);
+            let conflicts = access_set.get_conflicts_single(state.component_access());
+            if !conflicts.is_empty() {
+                let components = world.components();
+                let names = conflicts.into_iter().map(|id| components.get_info(id));
+                let names: Vec<_> = names.map(|info| info.map_or("", |i| i.name())).collect();
+                panic!(
+                    "DynamicQuery #{} in system {} accesses component(s) {} in a way that \
+                    conflicts with a previous query. Consider using `Without` filters to \
+                    make the queries disjoint.",
+                    self.states.len(),
+                    self.name,
+                    names.join(", "),
+                );
+            }
+            access_set.add(state.component_access().clone());
+            self.states.push(state);
+        }
+        let resources = self.resources.state(world);
+        access_set.add(resources.component_access().clone());
+        self.resources_state = Some(resources);
+        self.component_access = access_set.combined_access().clone();
+    }
+    fn update_archetype_component_access(&mut self, world: UnsafeWorldCell) {
+        assert!(
+            self.world_id == Some(world.id()),
+            "Encountered a mismatched World. A DynamicSystem cannot be used with Worlds \
+            other than the one it was initialized with."
+        );
+        for state in &mut self.states {
+            state.add_archetypes(world.archetypes());
+            let access = state.archetype_component_access();
+            self.archetype_component_access.extend(access);
+        }
+        if let Some(resources) = &mut self.resources_state {
+            resources.update_archetype_component_access(world);
+            let access = resources.archetype_component_access();
+            self.archetype_component_access.extend(access);
+        }
+    }
+    fn check_change_tick(&mut self, change_tick: Tick) {
+        let age = change_tick.get().wrapping_sub(self.last_run.get());
+        if age > Tick::MAX.get() {
+            self.last_run = Tick::new(change_tick.get().wrapping_sub(Tick::MAX.get()));
+            warn!(
+                "System '{}' has not run for {age} ticks. \
+                Changes older than {} ticks will not be detected.",
+                self.name,
+                Tick::MAX.get() - 1,
+            );
+        }
+    }
+    fn get_last_run(&self) -> Tick {
+        self.last_run
+    }
+    fn set_last_run(&mut self, last_run: Tick) {
+        self.last_run = last_run;
+    }
+}
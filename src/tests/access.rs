@@ -0,0 +1,87 @@
+use bevy::prelude::*;
+use bevy_ecs::query::{ReadOnlyWorldQuery, WorldQuery};
+use bevy_ecs::system::System;
+use test_log::test;
+
+use super::*;
+use crate::builder::{DFetches, DOr};
+use crate::{DynamicQuery, DynamicQueryParam, Ticks};
+
+fn typed_system<Q: WorldQuery, F: ReadOnlyWorldQuery>(_: Query<Q, F>) {}
+
+/// Check the access of a `DynamicState` is the same as that of a system with
+/// the equivalent typed `Query`.
+#[track_caller]
+fn test_same_access<Q, F>(world: &mut World)
+where
+    Q: DFetches + WorldQuery + 'static,
+    F: DOr + ReadOnlyWorldQuery + 'static,
+{
+    let query = DynamicQuery::from_query::<Query<Q, F>>(world);
+    let state = query.state(world);
+
+    let mut system = IntoSystem::into_system(typed_system::<Q, F>);
+    system.initialize(world);
+    system.update_archetype_component_access(world.as_unsafe_world_cell_readonly());
+
+    assert_eq!(system.component_access(), state.component_access().access());
+    assert_eq!(
+        system.archetype_component_access(),
+        state.archetype_component_access()
+    );
+}
+#[test]
+fn same_access_as_bevy() {
+    let mut world = test_world();
+    test_same_access::<&TableRegFancy, ()>(&mut world);
+    test_same_access::<(&mut TableRegTag, Option<&SetRegFancy>), Without<TableRegFancy>>(
+        &mut world,
+    );
+    test_same_access::<
+        (&SetRegTag, Option<&mut TableRegFancy>),
+        Or<(
+            Changed<Transform>,
+            (Without<Transform>, Added<SetRegSimple>),
+        )>,
+    >(&mut world);
+}
+#[test]
+fn builders_same_access() {
+    let mut world = test_world();
+    let from_query = make_query(&mut world).state(&mut world);
+    let from_methods = make_query2(&mut world).state(&mut world);
+    assert_eq!(
+        from_query.component_access(),
+        from_methods.component_access()
+    );
+}
+#[test]
+fn disjoint_mut_access() {
+    let mut world = test_world();
+    let state = |world: &mut World, query: DynamicQuery| query.state(world);
+
+    let q = DynamicQuery::from_query::<Query<&mut TableRegSimple, With<SetRegTag>>>(&mut world);
+    let with_tag = state(&mut world, q);
+    let q = DynamicQuery::from_query::<Query<&mut TableRegSimple, Without<SetRegTag>>>(&mut world);
+    let without_tag = state(&mut world, q);
+    let q = DynamicQuery::from_query::<Query<&TableRegSimple, ()>>(&mut world);
+    let read_all = state(&mut world, q);
+
+    assert!(with_tag.is_compatible(&without_tag));
+    assert!(!with_tag.is_compatible(&read_all));
+    assert!(!without_tag.is_compatible(&read_all));
+}
+#[test]
+fn param_iter_without() {
+    let mut world = test_world();
+    let query = DynamicQuery::from_query::<Query<&TableRegTag, Without<SetRegTag>>>(&mut world);
+    let mut state = query.state(&mut world);
+    let mut expected = world.query_filtered::<&TableRegTag, Without<SetRegTag>>();
+    let expected = expected.iter(&world).count();
+
+    let world = world.as_unsafe_world_cell();
+    // SAFETY: we have exclusive access to the world, and just created `state`.
+    let mut param = unsafe { DynamicQueryParam::new(world, &mut state, Ticks::of_world(world)) };
+    assert_eq!(param.iter_mut().count(), expected);
+    assert_eq!(param.iter().count(), expected);
+}
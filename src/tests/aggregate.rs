@@ -0,0 +1,136 @@
+use bevy::prelude::*;
+use test_log::test;
+
+use super::*;
+use crate::{Aggregate, AggregateError};
+
+fn aggregate_world() -> World {
+    let mut world = registered_world();
+    for (x, group) in [(4, 0), (1, 1), (3, 0), (1, 1), (6, 2)] {
+        world.spawn((TableRegSimple { x }, SetRegSimple { x: group }));
+    }
+    world.spawn(TableRegSimple { x: 5 });
+    world
+}
+
+fn as_usize(value: Option<Box<dyn Reflect>>) -> usize {
+    *value.unwrap().downcast::<usize>().unwrap()
+}
+
+#[test]
+fn aggregate_field() {
+    let mut world = aggregate_world();
+    let query = DynamicQueryBuilder::new(&mut world)
+        .component::<TableRegSimple>()
+        .build()
+        .unwrap();
+    let mut state = query.state(&mut world);
+
+    let mut aggregate = |aggregate| state.aggregate(&world, aggregate).unwrap();
+    assert_eq!(as_usize(aggregate(Aggregate::Count)), 6);
+    assert_eq!(as_usize(aggregate(Aggregate::Sum("TableRegSimple.x"))), 20);
+    assert_eq!(as_usize(aggregate(Aggregate::Min("TableRegSimple.x"))), 1);
+    assert_eq!(as_usize(aggregate(Aggregate::Max("TableRegSimple.x"))), 6);
+    let mean = aggregate(Aggregate::Mean("TableRegSimple.x")).unwrap();
+    assert_eq!(mean.downcast_ref::<f64>(), Some(&(20.0 / 6.0)));
+
+    let err = state.aggregate(&world, Aggregate::Sum("TableRegSimple"));
+    assert_eq!(
+        err.err(),
+        Some(AggregateError::NotNumeric("TableRegSimple".to_owned()))
+    );
+}
+#[test]
+fn aggregate_grouped() {
+    let mut world = aggregate_world();
+    let query = DynamicQueryBuilder::new(&mut world)
+        .component::<TableRegSimple>()
+        .optional::<SetRegSimple>()
+        .build()
+        .unwrap();
+    let mut state = query.state(&mut world);
+
+    let sums =
+        state.aggregate_grouped(&world, Aggregate::Sum("TableRegSimple.x"), "SetRegSimple.x");
+    let sums: Vec<_> = sums
+        .unwrap()
+        .into_iter()
+        .map(|group| {
+            (
+                *group.key.downcast::<usize>().unwrap(),
+                as_usize(group.value),
+            )
+        })
+        .collect();
+    // The entity without `SetRegSimple` is not in any group.
+    assert_eq!(sums.len(), 3);
+    assert!(sums.contains(&(0, 7)));
+    assert!(sums.contains(&(1, 2)));
+    assert!(sums.contains(&(2, 6)));
+
+    // Structs don't implement `reflect_hash`, their groups are found by
+    // comparing keys one by one.
+    let counts = state.aggregate_grouped(&world, Aggregate::Count, "SetRegSimple");
+    let counts: Vec<_> = counts
+        .unwrap()
+        .into_iter()
+        .map(|group| {
+            let key = group.key.reflect_ref();
+            let bevy_reflect::ReflectRef::Struct(key) = key else {
+                panic!("SetRegSimple is a struct");
+            };
+            let group_x = key.field("x").unwrap().downcast_ref::<usize>();
+            (*group_x.unwrap(), as_usize(group.value))
+        })
+        .collect();
+    assert_eq!(counts.len(), 3);
+    assert!(counts.contains(&(0, 2)));
+    assert!(counts.contains(&(1, 2)));
+    assert!(counts.contains(&(2, 1)));
+}
+#[test]
+fn aggregate_wide_and_mixed_numbers() {
+    #[derive(Component, Reflect)]
+    #[reflect(Component)]
+    enum Amount {
+        Int(u128),
+        Float(f32),
+    }
+    impl FromWorld for Amount {
+        fn from_world(_: &mut World) -> Self {
+            Amount::Int(0)
+        }
+    }
+    let mut world = registered_world();
+    world
+        .resource::<AppTypeRegistry>()
+        .write()
+        .register::<Amount>();
+    world.spawn(Amount::Int(u128::MAX - 1));
+    world.spawn(Amount::Int(3));
+    let query = DynamicQueryBuilder::new(&mut world)
+        .component::<Amount>()
+        .build()
+        .unwrap();
+    let mut state = query.state(&mut world);
+
+    let sum = state.aggregate(&world, Aggregate::Sum("Amount.0")).unwrap();
+    assert_eq!(sum.unwrap().downcast_ref::<u128>(), Some(&u128::MAX));
+    let min = state.aggregate(&world, Aggregate::Min("Amount.0")).unwrap();
+    assert_eq!(min.unwrap().downcast_ref::<u128>(), Some(&3));
+
+    world.spawn(Amount::Float(1.5));
+    let mean = state
+        .aggregate(&world, Aggregate::Mean("Amount.0"))
+        .unwrap();
+    assert!(mean.unwrap().downcast_ref::<f64>().is_some());
+    let err = state.aggregate(&world, Aggregate::Max("Amount.0"));
+    assert_eq!(
+        err.err(),
+        Some(AggregateError::MixedTypes {
+            path: "Amount.0".to_owned(),
+            first: "u128".to_owned(),
+            other: "f32".to_owned(),
+        })
+    );
+}
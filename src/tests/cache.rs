@@ -0,0 +1,45 @@
+use bevy::prelude::*;
+use test_log::test;
+
+use super::*;
+use crate::{update_dynamic_query_cache, DynamicQueryCache};
+
+#[test]
+fn cache_shares_and_updates_states() {
+    let mut world = test_world();
+    let mut cache = DynamicQueryCache::new();
+    let query1 = DynamicQuery::from_query::<Query<(&TableRegTag, &SetRegTag), ()>>(&mut world);
+    let query2 = DynamicQuery::from_query::<Query<(&SetRegTag, &TableRegTag), ()>>(&mut world);
+
+    let handle1 = cache.get_or_insert(&query1, world.archetypes());
+    let handle2 = cache.get_or_insert(&query2, world.archetypes());
+    assert_eq!(handle1, handle2);
+    assert_eq!(cache.len(), 1);
+    world.insert_resource(cache);
+
+    let count = |world: &mut World| {
+        world.resource_scope(|world, mut cache: Mut<DynamicQueryCache>| {
+            cache.state_mut(handle1).iter(world).count()
+        })
+    };
+    assert_eq!(count(&mut world), 1);
+
+    // New archetype
+    world.spawn((TableRegTag, SetRegTag, TableRegSimple::default()));
+    let mut schedule = Schedule::new();
+    schedule.add_systems(update_dynamic_query_cache);
+    schedule.run(&mut world);
+
+    assert_eq!(count(&mut world), 2);
+}
+#[test]
+#[should_panic = "another DynamicQueryCache"]
+fn cache_rejects_foreign_handles() {
+    let mut world = test_world();
+    let query = DynamicQuery::from_query::<Query<&TableRegTag, ()>>(&mut world);
+    let mut cache = DynamicQueryCache::new();
+    let mut other = DynamicQueryCache::new();
+    let handle = cache.get_or_insert(&query, world.archetypes());
+    other.get_or_insert(&query, world.archetypes());
+    other.state(handle);
+}
@@ -0,0 +1,95 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use test_log::test;
+
+use super::*;
+
+#[test]
+fn canonical_eq_hash() {
+    let mut world = test_world();
+    let query1 = DynamicQuery::from_query::<
+        Query<
+            (&SetRegTag, Option<&mut TableRegFancy>, &TableRegSimple),
+            Or<(
+                (With<SetRegSimple>, Without<TableRegTag>),
+                Added<SetRegFancy>,
+            )>,
+        >,
+    >(&mut world);
+    let query2 = DynamicQuery::from_query::<
+        Query<
+            (Option<&mut TableRegFancy>, &TableRegSimple, &SetRegTag),
+            Or<(
+                Added<SetRegFancy>,
+                (Without<TableRegTag>, With<SetRegSimple>),
+                Added<SetRegFancy>,
+            )>,
+        >,
+    >(&mut world);
+    let query3 = DynamicQuery::from_query::<
+        Query<
+            (&SetRegTag, &mut TableRegFancy, &TableRegSimple),
+            Or<(
+                (With<SetRegSimple>, Without<TableRegTag>),
+                Added<SetRegFancy>,
+            )>,
+        >,
+    >(&mut world);
+    assert_eq!(query1, query2);
+    assert_ne!(query1, query3);
+
+    let queries: HashSet<_> = [query1, query2, query3].into_iter().collect();
+    assert_eq!(queries.len(), 2);
+}
+#[test]
+fn canonical_predicate_values() {
+    use crate::CompareOp::{Gt, Lt};
+
+    let mut world = test_world();
+    let mut query = |values: [(usize, usize); 2]| {
+        let mut builder = DynamicQueryBuilder::new(&mut world);
+        builder.component::<TableRegSimple>();
+        for (first, second) in values {
+            builder.or(|b| {
+                b.predicate::<TableRegSimple>("x", Gt, first)
+                    .predicate::<TableRegSimple>("x", Gt, second)
+                    .predicate::<TableRegSimple>("x", Lt, 9_usize)
+            });
+        }
+        builder.build().unwrap()
+    };
+    // Same predicates and conjunctions, in different orders.
+    let query1 = query([(2, 5), (1, 3)]);
+    let query2 = query([(3, 1), (5, 2)]);
+    // Predicates only differing by value.
+    let query3 = query([(2, 6), (1, 3)]);
+    assert_eq!(query1, query2);
+    assert_ne!(query1, query3);
+
+    let queries: HashSet<_> = [query1, query2, query3].into_iter().collect();
+    assert_eq!(queries.len(), 2);
+
+    // Duplicates are removed even when not next to each other.
+    let duplicated = query([(4, 7), (4, 7)]);
+    let mut builder = DynamicQueryBuilder::new(&mut world);
+    let single = builder.component::<TableRegSimple>().or(|b| {
+        b.predicate::<TableRegSimple>("x", Gt, 4_usize)
+            .predicate::<TableRegSimple>("x", Lt, 9_usize)
+            .predicate::<TableRegSimple>("x", Gt, 7_usize)
+            .predicate::<TableRegSimple>("x", Gt, 4_usize)
+    });
+    assert_eq!(duplicated, single.build().unwrap());
+
+    // Numbers are ordered by value, not by their text.
+    let mut builder = DynamicQueryBuilder::new(&mut world);
+    let numbers = builder.component::<TableRegSimple>().or(|b| {
+        b.predicate::<TableRegSimple>("x", Gt, 10_usize)
+            .predicate::<TableRegSimple>("x", Gt, 9_usize)
+    });
+    let numbers = numbers.build().unwrap();
+    assert_eq!(
+        numbers.display(world.components()).compact().to_string(),
+        "TableRegSimple, TableRegSimple.x>9 TableRegSimple.x>10"
+    );
+}
@@ -0,0 +1,49 @@
+use test_log::test;
+
+use super::*;
+use crate::DynamicCursor;
+
+#[test]
+fn cursor_batches() {
+    let (mut world, entities) = simples_world(0..5, |_| false);
+    let query = DynamicQueryBuilder::new(&mut world)
+        .component_mut::<TableRegSimple>()
+        .build()
+        .unwrap();
+    let mut cursor = DynamicCursor::new(query.state(&mut world));
+
+    let batch: Vec<_> = cursor
+        .next_batch(&world, 2)
+        .iter()
+        .map(|r| simple_x(&r[0]).unwrap())
+        .collect();
+    assert_eq!(batch, [0, 1]);
+
+    // A new archetype, an entity spawned in the current archetype, a
+    // despawned entity already visited, and one not visited yet.
+    world.spawn((TableRegSimple { x: 10 }, SetRegTag));
+    world.spawn(TableRegSimple { x: 99 });
+    world.despawn(entities[0]);
+    world.despawn(entities[2]);
+
+    let batch: Vec<_> = cursor
+        .next_batch(&world, 10)
+        .iter()
+        .map(|r| simple_x(&r[0]).unwrap())
+        .collect();
+    // Despawning 0 moved 99 before 1 in the archetype, it is still returned.
+    assert_eq!(batch, [3, 4, 99, 10]);
+    assert!(cursor.next_batch(&world, 3).is_empty());
+
+    cursor.reset();
+    for row in cursor.next_batch_mut(&mut world, 10).iter_mut() {
+        row[0].downcast_mut::<TableRegSimple>().unwrap().x += 100;
+    }
+    cursor.reset();
+    let batch: Vec<_> = cursor
+        .next_batch(&world, 10)
+        .iter()
+        .map(|r| simple_x(&r[0]).unwrap())
+        .collect();
+    assert_eq!(batch, [199, 101, 104, 103, 110]);
+}
@@ -0,0 +1,136 @@
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+
+use bevy::prelude::*;
+use bevy_ecs::archetype::Archetype;
+use bevy_ecs::component::ComponentId;
+use test_log::test;
+
+use super::*;
+use crate::{ArchetypeIndex, DynamicFilter, DynamicQueryCache, DynamicState, FilterEntity};
+
+/// Rejects archetypes with more than `.0` components.
+#[derive(Clone, Debug, PartialEq)]
+struct MaxComponents(usize);
+impl DynamicFilter for MaxComponents {
+    fn matches_archetype(&self, archetype: &Archetype) -> bool {
+        archetype.components().count() <= self.0
+    }
+}
+
+/// Only entities in the shared set.
+#[derive(Clone, Debug, Default)]
+struct InSet(Arc<RwLock<HashSet<Entity>>>);
+impl PartialEq for InSet {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+impl DynamicFilter for InSet {
+    fn has_entity_check(&self) -> bool {
+        true
+    }
+    fn matches_entity(&self, entity: FilterEntity) -> bool {
+        self.0.read().unwrap().contains(&entity.id())
+    }
+}
+
+/// Only entities with a `TableRegSimple` with an even `x`.
+#[derive(Clone, Debug, PartialEq)]
+struct EvenX([ComponentId; 1]);
+impl DynamicFilter for EvenX {
+    fn has_entity_check(&self) -> bool {
+        true
+    }
+    fn matches_entity(&self, entity: FilterEntity) -> bool {
+        entity
+            .get::<TableRegSimple>()
+            .is_some_and(|simple| simple.x % 2 == 0)
+    }
+    fn reads(&self) -> &[ComponentId] {
+        &self.0
+    }
+}
+
+#[test]
+fn custom_archetype_filter() {
+    let (mut world, _) = simples_world((0..6).chain([10]), |x| x == 10);
+    let query = DynamicQueryBuilder::new(&mut world)
+        .component::<TableRegSimple>()
+        .or(|b| b.custom(MaxComponents(1)))
+        .build()
+        .unwrap();
+    assert_eq!(
+        query.display(world.components()).to_string(),
+        "Query<&TableRegSimple, @MaxComponents(1)>"
+    );
+    let mut state = query.state(&mut world);
+    assert_eq!(state.iter(&world).count(), 6);
+
+    let explanation = state.explain(&world);
+    let rejected = explanation.archetypes.iter().filter(|a| !a.matched());
+    assert!(rejected.count() > 0);
+}
+#[test]
+fn custom_entity_filter() {
+    let (mut world, simples) = simples_world((0..6).chain([10]), |x| x == 10);
+    let set = InSet::default();
+    set.0.write().unwrap().extend([simples[1], simples[4]]);
+    let query = DynamicQueryBuilder::new(&mut world)
+        .component::<TableRegSimple>()
+        .or(|b| b.custom(set.clone()))
+        .build()
+        .unwrap();
+    let mut state = query.state(&mut world);
+    assert_eq!(state.iter(&world).count(), 2);
+    assert!(state.get(&world, simples[0]).is_err());
+    assert!(state.get(&world, simples[4]).is_ok());
+
+    set.0.write().unwrap().insert(simples[0]);
+    assert_eq!(state.iter(&world).count(), 3);
+}
+#[test]
+fn custom_filter_cache() {
+    let (mut world, _) = simples_world((0..6).chain([10]), |x| x == 10);
+    let set = InSet::default();
+    let query = |world: &mut World, set: InSet| {
+        DynamicQueryBuilder::new(world)
+            .component::<TableRegSimple>()
+            .or(|b| b.custom(set))
+            .build()
+            .unwrap()
+    };
+    let first = query(&mut world, set.clone());
+    let same = query(&mut world, set.clone());
+    let other = query(&mut world, InSet::default());
+    assert_eq!(first, same);
+    assert_ne!(first, other);
+
+    let mut cache = DynamicQueryCache::new();
+    let handle = cache.get_or_insert(&first, world.archetypes());
+    assert_eq!(cache.get_or_insert(&first, world.archetypes()), handle);
+    assert_eq!(cache.get_or_insert(&same, world.archetypes()), handle);
+    assert_ne!(cache.get_or_insert(&other, world.archetypes()), handle);
+    assert_eq!(cache.len(), 2);
+}
+#[test]
+fn custom_filter_reads() {
+    let (mut world, _) = simples_world((0..6).chain([10]), |x| x == 10);
+    let simple_id = world.init_component::<TableRegSimple>();
+    let query = DynamicQueryBuilder::new(&mut world)
+        .component::<TableRegSimple>()
+        .or(|b| b.custom(EvenX([simple_id])))
+        .or(|b| b.with::<SetRegTag>())
+        .build()
+        .unwrap();
+    assert!(query
+        .state(&mut world)
+        .component_access()
+        .access()
+        .has_read(simple_id));
+
+    let index = ArchetypeIndex::new(world.archetypes());
+    let mut state = DynamicState::new_indexed(&query, world.archetypes(), &index);
+    // x in 0, 2, 4 and the `SetRegTag` entity.
+    assert_eq!(state.iter(&world).count(), 4);
+}
@@ -0,0 +1,56 @@
+use bevy::prelude::*;
+use pretty_assertions::assert_str_eq;
+use test_log::test;
+
+use super::*;
+use crate::{DynamicQueryDescription, FetchDescription, FilterDescription, ResolveError};
+
+fn described_query(world: &mut World) -> DynamicQuery {
+    DynamicQuery::from_query::<
+        Query<
+            (&mut TableRegSimple, Option<&SetRegFancy>),
+            Or<(
+                (With<SetRegTag>, Without<TableRegTag>),
+                Changed<TableRegFancy>,
+            )>,
+        >,
+    >(world)
+}
+#[test]
+fn describe_resolve() {
+    let mut world = test_world();
+    let query = described_query(&mut world);
+    let description = query.describe(world.components());
+    let resolved = description.resolve(&world).unwrap();
+
+    let comps = world.components();
+    assert_str_eq!(
+        resolved.display(comps).to_string(),
+        query.display(comps).to_string()
+    );
+}
+#[test]
+fn resolve_unresolved() {
+    let world = test_world();
+    let description = DynamicQueryDescription {
+        fetches: vec![
+            FetchDescription::Read("my_game::Health".to_owned()),
+            FetchDescription::Entity,
+        ],
+        filters: vec![vec![FilterDescription::With("my_game::Player".to_owned())]],
+    };
+    let expected = ["my_game::Health", "my_game::Player"].map(str::to_owned);
+    assert_eq!(
+        description.resolve(&world).unwrap_err(),
+        ResolveError::Unresolved(expected.to_vec()),
+    );
+}
+#[cfg(feature = "serde")]
+#[test]
+fn description_serde() {
+    let mut world = test_world();
+    let description = described_query(&mut world).describe(world.components());
+    let serialized = ron::to_string(&description).unwrap();
+    let deserialized: DynamicQueryDescription = ron::from_str(&serialized).unwrap();
+    assert_eq!(deserialized, description);
+}
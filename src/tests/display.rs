@@ -0,0 +1,41 @@
+use bevy::prelude::*;
+use pretty_assertions::assert_str_eq;
+use test_log::test;
+
+use super::*;
+
+#[test]
+fn display_query() {
+    let mut world = test_world();
+    let query = DynamicQuery::from_query::<
+        Query<
+            (Option<&SetRegFancy>, &mut TableRegSimple),
+            Or<(
+                (Without<TableRegTag>, With<SetRegTag>),
+                Changed<TableRegFancy>,
+            )>,
+        >,
+    >(&mut world);
+    let components = world.components();
+
+    assert_str_eq!(
+        query.display(components).to_string(),
+        "Query<(&mut TableRegSimple, Option<&SetRegFancy>), \
+            Or<((With<SetRegTag>, Without<TableRegTag>), Changed<TableRegFancy>)>>"
+    );
+    assert_str_eq!(
+        query.display(components).compact().to_string(),
+        "mut TableRegSimple ?SetRegFancy, SetRegTag !TableRegTag | >TableRegFancy"
+    );
+
+    let query = DynamicQuery::from_query::<Query<&TableRegTag, With<SetRegTag>>>(&mut world);
+    let components = world.components();
+    assert_str_eq!(
+        query.display(components).to_string(),
+        "Query<&TableRegTag, With<SetRegTag>>"
+    );
+    assert_str_eq!(
+        query.display(components).compact().to_string(),
+        "TableRegTag, SetRegTag"
+    );
+}
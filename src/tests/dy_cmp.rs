@@ -41,7 +41,7 @@ impl<T: PartialEq + Reflect + Clone> Dyown for Option<BevyMut<'_, T>> {
     type Owned = OptMut<T>;
 
     fn own(&self) -> Self::Owned {
-        OptMut(self.as_ref().map(|x| T::clone(x)))
+        OptMut(self.as_deref().cloned())
     }
 }
 
@@ -58,12 +58,12 @@ pub struct OptRef<T>(Option<T>);
 pub struct OptMut<T>(Option<T>);
 impl<T: PartialEq + Reflect> DyeqItem for Ref<T> {
     fn dyeq_item(&self, item: &DynamicItem) -> bool {
-        matches!(item, DynamicItem::Read(r) if r.downcast_ref() == Some(&self.0))
+        matches!(item, DynamicItem::Read(_, r) if r.downcast_ref() == Some(&self.0))
     }
 }
 impl<T: PartialEq + Reflect> DyeqItem for Mut<T> {
     fn dyeq_item(&self, item: &DynamicItem) -> bool {
-        matches!(item, DynamicItem::Mut(r) if r.downcast_ref() == Some(&self.0))
+        matches!(item, DynamicItem::Mut(_, r) if r.downcast_ref() == Some(&self.0))
     }
 }
 impl DyeqItem for Entity {
@@ -75,14 +75,14 @@ impl<T: PartialEq + Reflect> DyeqItem for OptRef<T> {
     fn dyeq_item(&self, item: &DynamicItem) -> bool {
         use DynamicItem::OptionRead;
         let opt: Option<Option<&T>> = self.0.as_ref().map(Some);
-        matches!(item, OptionRead(r) if r.map(<dyn Reflect>::downcast_ref) == opt)
+        matches!(item, OptionRead(_, r) if r.map(<dyn Reflect>::downcast_ref) == opt)
     }
 }
 impl<T: PartialEq + Reflect> DyeqItem for OptMut<T> {
     fn dyeq_item(&self, item: &DynamicItem) -> bool {
         use DynamicItem::OptionMut;
         let opt: Option<Option<&T>> = self.0.as_ref().map(Some);
-        matches!(item, OptionMut(r) if r.as_ref().map(|r| r.downcast_ref()) == opt)
+        matches!(item, OptionMut(_, r) if r.as_ref().map(|r| r.downcast_ref()) == opt)
     }
 }
 impl<T: Reflect> ShowReflect for Ref<T> {
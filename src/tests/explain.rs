@@ -0,0 +1,46 @@
+use bevy::prelude::*;
+use test_log::test;
+
+use super::*;
+use crate::explain::Rejection;
+
+#[test]
+fn explain_agrees_with_state() {
+    let mut world = test_world();
+    let query = DynamicQuery::from_query::<
+        Query<&TableRegFancy, Or<(Without<SetRegTag>, Added<SetRegSimple>)>>,
+    >(&mut world);
+    let state = query.state(&mut world);
+    let explanation = state.explain(&world);
+
+    assert_eq!(explanation.archetypes.len(), world.archetypes().len());
+    for archetype in &explanation.archetypes {
+        assert_eq!(
+            archetype.matched(),
+            state.archetype_ids.contains(archetype.id)
+        );
+    }
+    let tag_id = world.component_id::<SetRegTag>().unwrap();
+    let fancy_id = world.component_id::<TableRegFancy>().unwrap();
+    let tagged = explanation.archetypes.iter().find(|a| {
+        let archetype = &world.archetypes()[a.id];
+        archetype.contains(tag_id) && archetype.contains(fancy_id)
+    });
+    let tagged = tagged.unwrap();
+    assert!(!tagged.matched());
+    assert!(tagged.missing_fetches.is_empty());
+    // Conjunctions are sorted, `Added` comes before `Without`.
+    let [added, without] = &tagged.conjunctions[..] else {
+        panic!("Expected two conjunctions, got {:?}", tagged.conjunctions)
+    };
+    assert!(matches!(&without.rejections[..], [Rejection::HasWithout(c)] if c.id == tag_id));
+    assert!(matches!(
+        &added.rejections[..],
+        [Rejection::MissingAdded(_)]
+    ));
+    assert!(added.tick_filtered);
+
+    let text = explanation.to_string();
+    assert!(text.contains("has Without<SetRegTag>"), "{text}");
+    assert!(text.contains("missing fetched TableRegFancy"), "{text}");
+}
@@ -0,0 +1,91 @@
+use bevy::prelude::*;
+use test_log::test;
+
+use super::*;
+use crate::Snapshot;
+
+fn export_world() -> World {
+    let mut world = registered_world();
+    world.spawn((TableRegSimple { x: 1 }, SetRegSimple { x: 10 }));
+    world.spawn(TableRegSimple { x: 2 });
+    world
+}
+
+/// Write a snapshot of `world` with `write`, change the world, then read it
+/// back with `read` and apply it.
+fn round_trip<E: Debug>(
+    write: impl Fn(&Snapshot, &bevy_reflect::TypeRegistry) -> String,
+    read: impl Fn(
+        &str,
+        &bevy_reflect::TypeRegistry,
+        &bevy_ecs::component::Components,
+    ) -> Result<Snapshot, E>,
+) {
+    let mut world = export_world();
+    let query = DynamicQueryBuilder::new(&mut world)
+        .component_mut::<TableRegSimple>()
+        .optional_mut::<SetRegSimple>()
+        .build()
+        .unwrap();
+    let mut state = query.state(&mut world);
+
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let text = write(&state.snapshot(&world), &registry.read());
+
+    for mut simple in world.query::<&mut TableRegSimple>().iter_mut(&mut world) {
+        simple.x += 100;
+    }
+    world.query::<&mut SetRegSimple>().single_mut(&mut world).x = 0;
+
+    let snapshot = read(&text, &registry.read(), world.components()).unwrap();
+    let first = &snapshot.rows[0].values;
+    assert_eq!(first.len(), 2);
+    assert_eq!(
+        first[0].value.downcast_ref(),
+        Some(&TableRegSimple { x: 1 })
+    );
+    assert_eq!(snapshot.rows[1].values.len(), 1);
+    assert_eq!(state.apply_snapshot(&mut world, &snapshot), 2);
+
+    let mut simples: Vec<_> = world
+        .query::<&TableRegSimple>()
+        .iter(&world)
+        .map(|s| s.x)
+        .collect();
+    simples.sort_unstable();
+    assert_eq!(simples, [1, 2]);
+    let set = world.query::<&SetRegSimple>().single(&world);
+    assert_eq!(set.x, 10);
+}
+
+#[cfg(feature = "ron")]
+#[test]
+fn ron_round_trip() {
+    round_trip(|s, reg| s.to_ron(reg).unwrap(), Snapshot::from_ron);
+}
+#[cfg(feature = "json")]
+#[test]
+fn json_round_trip() {
+    round_trip(|s, reg| s.to_json(reg).unwrap(), Snapshot::from_json);
+}
+#[cfg(feature = "ron")]
+#[test]
+fn ron_format() {
+    let mut world = export_world();
+    let query = DynamicQueryBuilder::new(&mut world)
+        .component::<SetRegSimple>()
+        .build()
+        .unwrap();
+    let snapshot = query.state(&mut world).snapshot(&world);
+    let registry = world.resource::<AppTypeRegistry>().read();
+    let ron = ron::to_string(&crate::SnapshotSerializer::new(&snapshot, &registry)).unwrap();
+    let entity = snapshot.rows[0].entity.to_bits();
+    let expected = format!(
+        r#"[(entity:{entity},components:{{"bevy_mod_dynamic_query::tests::SetRegSimple":(x:10)}})]"#
+    );
+    assert_eq!(ron, expected);
+
+    let unknown = r#"[(entity:0,components:{"bevy_mod_dynamic_query::tests::TableRegTag":()})]"#;
+    let err = Snapshot::from_ron(unknown, &registry, world.components());
+    assert!(err.is_err());
+}
@@ -0,0 +1,37 @@
+use bevy::prelude::*;
+use test_log::test;
+
+use super::*;
+use crate::{ArchetypeIndex, DynamicState};
+
+#[track_caller]
+fn test_same_matches<Q, F>(world: &mut World)
+where
+    Q: DFetches + WorldQuery + 'static,
+    F: DOr + ReadOnlyWorldQuery + 'static,
+{
+    let query = DynamicQuery::from_query::<Query<Q, F>>(world);
+    let index = ArchetypeIndex::new(world.archetypes());
+    let mut scanned = DynamicState::new(&query, world.archetypes());
+    let mut indexed = DynamicState::new_indexed(&query, world.archetypes(), &index);
+
+    for archetype in world.archetypes().iter() {
+        let id = archetype.id();
+        let (scanned, indexed) = (&scanned.archetype_ids, &indexed.archetype_ids);
+        assert_eq!(scanned.contains(id), indexed.contains(id));
+    }
+    assert_eq!(scanned.iter(world).count(), indexed.iter(world).count());
+}
+#[test]
+fn indexed_same_as_scanned() {
+    let mut world = test_world();
+    test_same_matches::<&TableRegFancy, ()>(&mut world);
+    test_same_matches::<(&SetRegTag, Option<&mut TableRegTag>), Without<TableRegFancy>>(&mut world);
+    test_same_matches::<
+        (&SetRegTag, Option<&mut TableRegFancy>),
+        Or<(
+            Changed<Transform>,
+            (Without<Transform>, Added<SetRegSimple>),
+        )>,
+    >(&mut world);
+}
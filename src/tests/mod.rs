@@ -3,7 +3,6 @@ use std::str::FromStr;
 
 use bevy::prelude::*;
 use bevy_ecs::query::{ReadOnlyWorldQuery, WorldQuery};
-use bevy_ecs::world::unsafe_world_cell::UnsafeWorldCell;
 use bevy_ecs::{component::StorageType, system::SystemState};
 use cuicui_dsl::{dsl, DslBundle};
 use pretty_assertions::assert_str_eq;
@@ -11,10 +10,36 @@ use test_log::test;
 
 use crate::builder::{DFetches, DOr};
 use crate::pretty_print::{DynShow, DynShowT, ShowReflect};
-use crate::{DQuery, DynamicQuery, DynamicQueryBuilder};
+use crate::{DQuery, DynamicItem, DynamicQuery, DynamicQueryBuilder};
 use dy_cmp::{Dyeq, Dyown};
 
+mod access;
+mod aggregate;
+mod cache;
+mod canonical;
+mod cursor;
+mod custom_filter;
+mod description;
+mod display;
 mod dy_cmp;
+mod explain;
+#[cfg(any(feature = "ron", feature = "json"))]
+mod export;
+mod index;
+mod order;
+mod param_set;
+#[cfg(feature = "parse")]
+mod parse;
+mod predicate;
+mod rebind;
+mod resources;
+mod row;
+#[cfg(feature = "rand")]
+mod sample;
+mod snapshot;
+mod sort;
+mod system;
+mod ticks;
 
 #[derive(Clone, Copy, Debug, Default)]
 enum Complexity {
@@ -103,10 +128,12 @@ impl DslBundle for Dsl {
 
 #[derive(Component, Debug, Default)]
 struct TableNorgTag;
+#[allow(dead_code)]
 #[derive(Component, Debug, Default)]
 struct TableNorgSimple {
     x: usize,
 }
+#[allow(dead_code)]
 #[derive(Component, Debug, Default)]
 struct TableNorgFancy {
     zoo: Box<usize>,
@@ -141,11 +168,13 @@ struct TableRegFancy {
 #[derive(Component, Debug, Default)]
 #[component(storage = "SparseSet")]
 struct SetNorgTag;
+#[allow(dead_code)]
 #[derive(Component, Debug, Default)]
 #[component(storage = "SparseSet")]
 struct SetNorgSimple {
     x: usize,
 }
+#[allow(dead_code)]
 #[derive(Component, Debug, Default)]
 #[component(storage = "SparseSet")]
 struct SetNorgFancy {
@@ -209,13 +238,13 @@ fn make_query(world: &mut World) -> DynamicQuery {
     >(world)
 }
 
-fn test_world() -> World {
+/// An empty world with an [`AppTypeRegistry`] where all the `*Reg*` test
+/// components are registered.
+fn registered_world() -> World {
     let mut world = World::new();
     let reg = AppTypeRegistry::default();
     {
         let mut reg = reg.write();
-        reg.register::<Vec<Transform>>();
-        reg.register::<Option<Entity>>();
         reg.register::<SetRegTag>();
         reg.register::<SetRegSimple>();
         reg.register::<SetRegFancy>();
@@ -224,6 +253,38 @@ fn test_world() -> World {
         reg.register::<TableRegFancy>();
     }
     world.insert_resource(reg);
+    world
+}
+/// [`registered_world`] with a `TableRegSimple { x }` entity for each of `xs`,
+/// which also has a `SetRegTag` if `tagged(x)`.
+fn simples_world(
+    xs: impl IntoIterator<Item = usize>,
+    tagged: impl Fn(usize) -> bool,
+) -> (World, Vec<Entity>) {
+    let mut world = registered_world();
+    let mut spawn = |x| {
+        let mut entity = world.spawn(TableRegSimple { x });
+        if tagged(x) {
+            entity.insert(SetRegTag);
+        }
+        entity.id()
+    };
+    let entities = xs.into_iter().map(&mut spawn).collect();
+    (world, entities)
+}
+/// The `x` of a `TableRegSimple` item, `None` if it is absent or another
+/// component.
+fn simple_x(item: &DynamicItem) -> Option<usize> {
+    item.downcast_ref::<TableRegSimple>().map(|simple| simple.x)
+}
+
+fn test_world() -> World {
+    let mut world = registered_world();
+    {
+        let mut reg = world.resource::<AppTypeRegistry>().write();
+        reg.register::<Vec<Transform>>();
+        reg.register::<Option<Entity>>();
+    }
     {
         let mut state = SystemState::<Commands>::new(&mut world);
         let mut cmds = state.get_mut(&mut world);
@@ -275,7 +336,7 @@ fn test_query_get<DQ: DQuery>(
     let mut state = query.state(world);
     let value = state.get_mut(world, entity).unwrap();
 
-    let equivalent = expected.dyeq(value);
+    let equivalent = expected.dyeq(&value);
     if !equivalent {
         let expected = format!("{:?}", DynShowT(&expected));
         let actual = format!("{:?}", DynShow::new(value));
@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use test_log::test;
+
+use super::*;
+use crate::builder::NamedDynamicBuilder;
+use crate::{DynamicRow, DynamicState, FieldError, StableOrder};
+
+fn entities(state: &mut DynamicState, world: &World) -> Vec<Entity> {
+    let entity = |row: DynamicRow| row[0].entity().unwrap();
+    state.iter(world).map(entity).collect()
+}
+
+#[test]
+fn stable_entity_order() {
+    let mut world = registered_world();
+    let e0 = world.spawn((TableRegSimple { x: 0 }, SetRegTag)).id();
+    let e1 = world.spawn(TableRegSimple { x: 1 }).id();
+    let e2 = world.spawn((TableRegSimple { x: 2 }, SetRegTag)).id();
+
+    let reg = world.resource::<AppTypeRegistry>().clone();
+    let query = NamedDynamicBuilder::new(&reg.read(), world.components())
+        .entity()
+        .component("TableRegSimple")
+        .build()
+        .unwrap();
+    let mut state = query.state(&mut world);
+    assert_eq!(entities(&mut state, &world), [e0, e2, e1]);
+
+    state
+        .set_stable_order(&world, Some(StableOrder::Entity))
+        .unwrap();
+    assert_eq!(state.stable_order(), Some(&StableOrder::Entity));
+    assert_eq!(entities(&mut state, &world), [e0, e1, e2]);
+
+    world.despawn(e1);
+    let e3 = world.spawn((TableRegSimple { x: 3 }, TableRegTag)).id();
+    state.add_archetypes(world.archetypes());
+    assert_eq!(entities(&mut state, &world), [e0, e2, e3]);
+
+    state.set_stable_order(&world, None).unwrap();
+    assert_eq!(entities(&mut state, &world), [e0, e2, e3]);
+}
+
+#[test]
+fn stable_key_order() {
+    let mut world = registered_world();
+    let e0 = world.spawn((TableRegSimple { x: 5 }, SetRegTag)).id();
+    let e1 = world.spawn(TableRegSimple { x: 1 }).id();
+    let e2 = world.spawn((TableRegSimple { x: 3 }, SetRegTag)).id();
+
+    let reg = world.resource::<AppTypeRegistry>().clone();
+    let query = NamedDynamicBuilder::new(&reg.read(), world.components())
+        .entity()
+        .component("TableRegSimple")
+        .build()
+        .unwrap();
+    let mut state = query.state(&mut world);
+
+    let order = StableOrder::Key("TableRegSimple.x".to_owned());
+    state.set_stable_order(&world, Some(order)).unwrap();
+    assert_eq!(entities(&mut state, &world), [e1, e2, e0]);
+
+    world.get_mut::<TableRegSimple>(e1).unwrap().x = 4;
+    assert_eq!(entities(&mut state, &world), [e2, e1, e0]);
+
+    let order = StableOrder::Key("SetRegTag".to_owned());
+    assert_eq!(
+        state.set_stable_order(&world, Some(order)),
+        Err(FieldError::NotFetched("SetRegTag".to_owned()))
+    );
+}
+
+#[test]
+fn stable_order_incremental() {
+    let mut world = registered_world();
+    let reg = world.resource::<AppTypeRegistry>().clone();
+    world.init_component::<TableRegSimple>();
+    let query = NamedDynamicBuilder::new(&reg.read(), world.components())
+        .entity()
+        .component("TableRegSimple")
+        .build()
+        .unwrap();
+    let mut state = query.state(&mut world);
+    let order = StableOrder::Key("TableRegSimple.x".to_owned());
+    state.set_stable_order(&world, Some(order)).unwrap();
+
+    // The order a full sort by key then entity gives.
+    let expected = |world: &mut World| {
+        let mut all: Vec<_> = world
+            .query::<(Entity, &TableRegSimple)>()
+            .iter(world)
+            .map(|(entity, simple)| (simple.x, entity))
+            .collect();
+        all.sort_unstable();
+        all.into_iter()
+            .map(|(_, entity)| entity)
+            .collect::<Vec<_>>()
+    };
+    let spawned: Vec<_> = (0..8)
+        .map(|i| world.spawn(TableRegSimple { x: (i * 5) % 8 }).id())
+        .collect();
+    state.add_archetypes(world.archetypes());
+    assert_eq!(entities(&mut state, &world), expected(&mut world));
+
+    // Despawning moves the archetype's last entity to the despawned row.
+    world.despawn(spawned[1]);
+    world.get_mut::<TableRegSimple>(spawned[6]).unwrap().x = 0;
+    assert_eq!(entities(&mut state, &world), expected(&mut world));
+
+    // Move between matched archetypes, with and without changing the key.
+    world.entity_mut(spawned[2]).insert(SetRegTag);
+    world.entity_mut(spawned[3]).insert(SetRegTag);
+    world.get_mut::<TableRegSimple>(spawned[3]).unwrap().x = 7;
+    state.add_archetypes(world.archetypes());
+    assert_eq!(entities(&mut state, &world), expected(&mut world));
+
+    world.spawn(TableRegSimple { x: 3 });
+    world.entity_mut(spawned[4]).remove::<TableRegSimple>();
+    world.get_mut::<TableRegSimple>(spawned[0]).unwrap().x = 9;
+    assert_eq!(entities(&mut state, &world), expected(&mut world));
+}
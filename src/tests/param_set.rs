@@ -0,0 +1,48 @@
+use bevy::prelude::*;
+use test_log::test;
+
+use super::*;
+use crate::DynamicParamSet;
+
+fn param_set(world: &mut World) -> DynamicParamSet {
+    let queries = [
+        DynamicQuery::from_query::<Query<&mut TableRegSimple, With<SetRegTag>>>(world),
+        DynamicQuery::from_query::<Query<&mut TableRegSimple, Without<SetRegTag>>>(world),
+        DynamicQuery::from_query::<Query<&TableRegSimple, ()>>(world),
+    ];
+    DynamicParamSet::new(&queries, world)
+}
+#[test]
+fn param_set_conflicts() {
+    let mut world = test_world();
+    let set = param_set(&mut world);
+
+    assert!(!set.conflicts(0, 1));
+    assert!(set.conflicts(0, 2));
+    assert!(set.conflicts(2, 1));
+    assert!(!set.conflicts(2, 2));
+}
+#[test]
+#[should_panic = "out of bound"]
+fn param_set_conflicts_out_of_bound() {
+    let mut world = test_world();
+    // (0, 3) would otherwise read the bit of (1, 0).
+    param_set(&mut world).conflicts(0, 3);
+}
+#[test]
+fn param_set_borrow() {
+    let mut world = test_world();
+    let mut set = param_set(&mut world);
+    let total = world.query::<&TableRegSimple>().iter(&world).count();
+
+    assert!(set.get_many(&mut world, [0, 2]).is_none());
+    assert!(set.get_many(&mut world, [1, 1]).is_none());
+    assert!(set.get_many(&mut world, [1, 3]).is_none());
+
+    let [mut with, mut without] = set.get_many(&mut world, [0, 1]).unwrap();
+    let with_count = with.iter_mut().count();
+    let without_count = without.iter_mut().count();
+    assert_eq!(with_count + without_count, total);
+
+    assert_eq!(set.p(&mut world, 2).iter().count(), total);
+}
@@ -0,0 +1,182 @@
+use bevy::prelude::*;
+use pretty_assertions::assert_str_eq;
+use test_log::test;
+
+use super::*;
+use crate::builder::NamedDynamicBuilder;
+use crate::parse::{AndFilterStr as F, AndFiltersStr, FetchStr, ParseErrorKind, QueryStr};
+use crate::CompareOp;
+
+#[test]
+fn parse_nested_or() {
+    let rust_like = "Query<(&A, Entity, Option<&mut B>,), \
+        (Without<C>, Or<(With<D>, (Added<E>, Changed<F>))>)>";
+    let compact = "A Entity ?mut B, !C (D | +E >F)";
+    let expected = QueryStr {
+        fetches: vec![
+            FetchStr::Read("A"),
+            FetchStr::Entity,
+            FetchStr::OptionMut("B"),
+        ],
+        filters: vec![
+            AndFiltersStr(vec![F::Without("C"), F::With("D")]),
+            AndFiltersStr(vec![F::Without("C"), F::Added("E"), F::Changed("F")]),
+        ],
+    };
+    assert_eq!(QueryStr::parse(rust_like), Ok(expected.clone()));
+    assert_eq!(QueryStr::parse(compact), Ok(expected));
+}
+#[test]
+fn parse_predicates() {
+    let rust_like = "Query<&A, (B.x.y < 10, Or<(B#0 == (1, \"a b\"), C != [1, 2])>)>";
+    let compact = "A, B.x.y<10 (B#0==(1,\"a b\") | C!=[1,2])";
+    let literals = |text| {
+        let parsed = QueryStr::parse(text).unwrap();
+        let predicates = parsed.filters.iter().flat_map(|c| &c.0);
+        let literals = predicates.filter_map(|filter| match filter {
+            F::Predicate { literal, .. } => Some(literal.replace(' ', "")),
+            _ => None,
+        });
+        literals.collect::<Vec<_>>()
+    };
+    let expected = ["10", "(1,\"ab\")", "10", "[1,2]"];
+    assert_eq!(literals(rust_like), expected);
+    assert_eq!(literals(compact), expected);
+    assert_eq!(
+        QueryStr::parse("A, B>C").unwrap().filters,
+        [AndFiltersStr(vec![F::Predicate {
+            component: "B",
+            path: "",
+            op: CompareOp::Gt,
+            literal: "C"
+        }])]
+    );
+    assert_eq!(
+        QueryStr::parse("A, B >C").unwrap().filters,
+        [AndFiltersStr(vec![F::With("B"), F::Changed("C")])]
+    );
+
+    let world = test_world();
+    let reg = world.resource::<AppTypeRegistry>().read();
+    let input = "TableRegTag, TableRegSimple.y<3";
+    let err = DynamicQuery::parse(input, &reg, world.components()).unwrap_err();
+    assert!(matches!(err.kind, ParseErrorKind::Predicate(_)));
+    assert_eq!(err.span, 13..31);
+}
+#[test]
+fn parse_errors() {
+    let err = QueryStr::parse("Query<(&A, &mut B), With<C>").unwrap_err();
+    assert_eq!(err.span, 27..27);
+
+    let err = QueryStr::parse("A ?B, C | !").unwrap_err();
+    assert_eq!(err.span, 10..11);
+
+    let world = test_world();
+    let reg = world.resource::<AppTypeRegistry>().read();
+    let input = "TableRegTag mut Nothing, SetRegTag";
+    let err = DynamicQuery::parse(input, &reg, world.components()).unwrap_err();
+    assert_eq!(err.span, 16..23);
+    assert_eq!(
+        err.kind,
+        ParseErrorKind::UnknownComponent("Nothing".to_owned())
+    );
+
+    let input = "TableRegTag mut TableRegTag";
+    let err = DynamicQuery::parse(input, &reg, world.components()).unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::Duplicate);
+    assert_eq!(err.span, 16..27);
+
+    let err = QueryStr::parse("A, B !B").unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::NeverMatches);
+}
+#[test]
+fn parse_simplified_conjunctions() {
+    let rust_like = "Query<&A, (With<B>, Or<(Without<B>, Changed<B>, (Added<C>, Changed<C>))>)>";
+    let compact = "A, B (!B | >B | +C >C)";
+    let expected = QueryStr {
+        fetches: vec![FetchStr::Read("A")],
+        filters: vec![
+            AndFiltersStr(vec![F::Changed("B")]),
+            AndFiltersStr(vec![F::With("B"), F::Added("C")]),
+        ],
+    };
+    assert_eq!(QueryStr::parse(rust_like), Ok(expected.clone()));
+    assert_eq!(QueryStr::parse(compact), Ok(expected));
+
+    let world = test_world();
+    let reg = world.resource::<AppTypeRegistry>().read();
+    let input = "TableRegTag, SetRegTag (!SetRegTag | TableRegFancy)";
+    let query = DynamicQuery::parse(input, &reg, world.components()).unwrap();
+    let expected = "TableRegTag, SetRegTag TableRegFancy";
+    assert_eq!(
+        query.display(world.components()).compact().to_string(),
+        expected
+    );
+}
+#[track_caller]
+fn round_trip(query: DynamicQuery, world: &World) {
+    let reg = world.resource::<AppTypeRegistry>().read();
+    let comps = world.components();
+    for text in [
+        query.display(comps).to_string(),
+        query.display(comps).compact().to_string(),
+    ] {
+        let parsed = DynamicQuery::parse(&text, &reg, comps).unwrap();
+        assert_str_eq!(
+            parsed.display(comps).to_string(),
+            query.display(comps).to_string()
+        );
+    }
+}
+#[test]
+fn display_round_trip() {
+    let mut world = test_world();
+    world
+        .resource::<AppTypeRegistry>()
+        .write()
+        .register::<Transform>();
+    round_trip(make_query(&mut world), &world);
+    round_trip(make_query2(&mut world), &world);
+    let query = DynamicQuery::from_query::<
+        Query<
+            (&mut TableRegSimple, Option<&SetRegFancy>),
+            Or<(
+                (With<SetRegTag>, Without<TableRegTag>),
+                Changed<TableRegFancy>,
+            )>,
+        >,
+    >(&mut world);
+    round_trip(query, &world);
+
+    let reg = world.resource::<AppTypeRegistry>().clone();
+    let query = NamedDynamicBuilder::new(&reg.read(), world.components())
+        .component("TableRegSimple")
+        .or(|b| b.predicate("TableRegSimple", "x", CompareOp::Gt, "2"))
+        .or(|b| {
+            b.with("SetRegTag")
+                .predicate("TableRegFancy", "zoo", CompareOp::Ne, " 4 ")
+        })
+        .build()
+        .unwrap();
+    assert_eq!(
+        query.display(world.components()).compact().to_string(),
+        "TableRegSimple, TableRegSimple.x>2 | SetRegTag TableRegFancy.zoo!=4"
+    );
+    round_trip(query, &world);
+}
+#[test]
+fn empty_fetches_round_trip() {
+    let mut world = test_world();
+    let query = DynamicQuery::from_query::<Query<(), With<SetRegTag>>>(&mut world);
+    assert_eq!(
+        query.display(world.components()).compact().to_string(),
+        "(), SetRegTag"
+    );
+    round_trip(query, &world);
+    let query = DynamicQuery::from_query::<Query<(), ()>>(&mut world);
+    assert_eq!(
+        query.display(world.components()).compact().to_string(),
+        "()"
+    );
+    round_trip(query, &world);
+}
@@ -0,0 +1,146 @@
+use bevy::prelude::*;
+use test_log::test;
+
+use super::*;
+use crate::{CompareOp, DynamicRow};
+
+/// Entities with `TableRegSimple { x }` for `x` in `0..10`, every odd `x`
+/// also has a `SetRegTag`.
+fn predicate_world() -> World {
+    let (mut world, _) = simples_world(0..10, |x| x % 2 == 1);
+    world.spawn(SetRegTag);
+    world
+}
+fn xs(query: &DynamicQuery, world: &mut World) -> Vec<usize> {
+    let mut state = query.state(world);
+    let x = |row: DynamicRow| simple_x(&row[0]).unwrap();
+    let mut xs: Vec<_> = state.iter(world).map(x).collect();
+    xs.sort_unstable();
+    xs
+}
+
+#[test]
+fn predicate_filters_entities() {
+    let mut world = predicate_world();
+    let query = DynamicQueryBuilder::new(&mut world)
+        .component::<TableRegSimple>()
+        .or(|b| b.predicate::<TableRegSimple>("x", CompareOp::Ge, 6_usize))
+        .build()
+        .unwrap();
+    assert_eq!(xs(&query, &mut world), [6, 7, 8, 9]);
+
+    let query = DynamicQueryBuilder::new(&mut world)
+        .component::<TableRegSimple>()
+        .or(|b| {
+            b.predicate::<TableRegSimple>("x", CompareOp::Gt, 2_usize)
+                .predicate::<TableRegSimple>("x", CompareOp::Ne, 5_usize)
+                .with::<SetRegTag>()
+        })
+        .or(|b| b.predicate::<TableRegSimple>("x", CompareOp::Eq, 0_usize))
+        .build()
+        .unwrap();
+    assert_eq!(xs(&query, &mut world), [0, 3, 7, 9]);
+}
+#[test]
+fn predicate_requires_component() {
+    let mut world = predicate_world();
+    let query = DynamicQueryBuilder::new(&mut world)
+        .or(|b| b.predicate::<TableRegSimple>("x", CompareOp::Lt, 100_usize))
+        .or(|b| b.without::<SetRegTag>())
+        .build()
+        .unwrap();
+    // All 10 `TableRegSimple`, but not the entity with only a `SetRegTag`.
+    let mut state = query.state(&mut world);
+    assert_eq!(state.iter(&world).count(), 10);
+
+    let tag_only = world.spawn(SetRegTag).id();
+    let mut state = query.state(&mut world);
+    assert!(state.get(&world, tag_only).is_err());
+}
+#[test]
+fn predicate_wrong_type_never_matches() {
+    let mut world = predicate_world();
+    let query = DynamicQueryBuilder::new(&mut world)
+        .component::<TableRegSimple>()
+        .or(|b| b.predicate::<TableRegSimple>("x", CompareOp::Ge, 0_u32))
+        .build()
+        .unwrap();
+    assert_eq!(xs(&query, &mut world), [0_usize; 0]);
+}
+#[cfg(feature = "parse")]
+#[test]
+fn predicate_literal() {
+    use crate::builder::NamedDynamicBuilder;
+    use crate::ResolveError;
+
+    let mut world = predicate_world();
+    let reg = world.resource::<AppTypeRegistry>().clone();
+    let query = NamedDynamicBuilder::new(&reg.read(), world.components())
+        .component("TableRegSimple")
+        .or(|b| b.predicate("TableRegSimple", "x", CompareOp::Le, "2"))
+        .build()
+        .unwrap();
+    assert_eq!(xs(&query, &mut world), [0, 1, 2]);
+
+    let description = query.describe(world.components());
+    let resolved = description.resolve(&world).unwrap();
+    assert_eq!(resolved, query);
+
+    let typed = DynamicQueryBuilder::new(&mut world)
+        .or(|b| b.predicate::<TableRegSimple>("x", CompareOp::Le, 2_usize))
+        .build()
+        .unwrap();
+    let description = typed.describe(world.components());
+    let err = description.resolve(&world).unwrap_err();
+    assert!(matches!(err, ResolveError::Predicate(..)));
+}
+#[cfg(feature = "parse")]
+#[test]
+fn predicate_literal_enum_field() {
+    use crate::builder::NamedDynamicBuilder;
+    use bevy_reflect::ReflectFromPtr;
+
+    use crate::{Predicate, PredicateError};
+
+    /// No `Default`, and fields are not in the `FromWorld` variant.
+    #[derive(Component, Reflect)]
+    #[reflect(Component)]
+    enum Job {
+        Idle,
+        Worker { level: usize },
+        Carrier(usize, Vec<usize>),
+    }
+    impl FromWorld for Job {
+        fn from_world(_: &mut World) -> Self {
+            Job::Idle
+        }
+    }
+    let mut world = predicate_world();
+    let reg = world.resource::<AppTypeRegistry>().clone();
+    reg.write().register::<Job>();
+    reg.write().register::<Vec<usize>>();
+    world.spawn(Job::Idle);
+    world.spawn(Job::Worker { level: 1 });
+    world.spawn(Job::Worker { level: 3 });
+    world.spawn(Job::Carrier(4, vec![1, 5]));
+
+    let mut count = |path, op, literal| {
+        let query = NamedDynamicBuilder::new(&reg.read(), world.components())
+            .component("Job")
+            .or(|b| b.predicate("Job", path, op, literal))
+            .build()
+            .unwrap();
+        let mut state = query.state(&mut world);
+        state.iter(&world).count()
+    };
+    assert_eq!(count("level", CompareOp::Ge, "2"), 1);
+    assert_eq!(count("0", CompareOp::Gt, "2"), 1);
+    assert_eq!(count("1[1]", CompareOp::Eq, "5"), 1);
+
+    let reg = reg.read();
+    let from_ptr = reg.get_type_data::<ReflectFromPtr>(std::any::TypeId::of::<Job>());
+    let from_ptr = from_ptr.unwrap().clone();
+    let data = crate::builder::FetchData { id: world.init_component::<Job>(), from_ptr };
+    let err = Predicate::from_literal(&reg, data, "speed", CompareOp::Eq, "1");
+    assert!(matches!(err, Err(PredicateError::Path(_))));
+}
@@ -0,0 +1,29 @@
+use bevy::prelude::*;
+use test_log::test;
+
+use super::*;
+use crate::RebindError;
+
+#[test]
+fn rebind_other_world() {
+    let mut world = test_world();
+    let query = DynamicQuery::from_query::<
+        Query<(&TableRegFancy, Option<&mut TableRegSimple>), Without<SetRegTag>>,
+    >(&mut world);
+
+    // Register components in a different order, so that ids differ.
+    let mut other = World::new();
+    other.init_component::<Transform>();
+    other.spawn((SetRegTag, TableRegFancy::default()));
+    other.spawn(TableRegFancy::default());
+    other.spawn((TableRegSimple::default(), TableRegFancy::default()));
+    let fancy_id = |world: &World| world.component_id::<TableRegFancy>();
+    assert_ne!(fancy_id(&world), fancy_id(&other));
+
+    let rebound = query.rebind(&world, &other).unwrap();
+    let mut state = rebound.state(&mut other);
+    assert_eq!(state.iter_mut(&mut other).count(), 2);
+
+    let err = query.rebind(&world, &World::new()).unwrap_err();
+    assert!(matches!(err, RebindError::NotInTarget(_)));
+}
@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use test_log::test;
+
+use super::*;
+use crate::builder::{NamedResourcesBuilder, NamedResourcesError};
+use crate::{DynamicItem, DynamicQuery, DynamicQueryError, DynamicResources, DynamicSystem};
+
+#[derive(Resource, Reflect, Debug, Default, PartialEq)]
+#[reflect(Resource)]
+struct Step(usize);
+
+#[derive(Resource, Reflect, Debug, Default, PartialEq)]
+#[reflect(Resource)]
+struct Missing;
+
+fn resource_world() -> World {
+    let mut world = test_world();
+    world.init_resource::<Step>();
+    world.init_resource::<Missing>();
+    world.remove_resource::<Missing>();
+    let mut registry = world.resource::<AppTypeRegistry>().write();
+    registry.register::<Step>();
+    registry.register::<Missing>();
+    drop(registry);
+    world
+}
+fn named_resources(world: &World, f: impl FnOnce(&mut NamedResourcesBuilder)) -> DynamicResources {
+    let registry = world.resource::<AppTypeRegistry>().read();
+    let mut builder = NamedResourcesBuilder::new(&registry, world.components());
+    f(&mut builder);
+    builder.build().unwrap()
+}
+
+#[test]
+fn named_resource_mut() {
+    let mut world = resource_world();
+    let resources = named_resources(&world, |b| {
+        b.resource_mut("Step").optional("Missing");
+    });
+    let mut state = resources.state(&world);
+    world.clear_trackers();
+
+    let items = state.get_mut(&mut world).unwrap();
+    let [DynamicItem::Mut(_, step), DynamicItem::OptionRead(_, None)] = items else {
+        panic!("unexpected items: {items:?}");
+    };
+    step.downcast_mut::<Step>().unwrap().0 = 7;
+
+    assert_eq!(world.resource::<Step>(), &Step(7));
+    // Fetching mutably doesn't mark the resource as changed.
+    assert!(!world.is_resource_changed::<Step>());
+}
+#[test]
+fn named_resource_errors() {
+    let world = resource_world();
+    let registry = world.resource::<AppTypeRegistry>().read();
+    let mut builder = NamedResourcesBuilder::new(&registry, world.components());
+
+    builder.resource("Step").resource_mut("Step");
+    assert_eq!(builder.build().err(), Some(NamedResourcesError::Duplicate));
+
+    builder
+        .resource("Step")
+        .resource("Unknown")
+        .optional("TableRegSimple");
+    let unresolved = vec!["Unknown".to_owned(), "TableRegSimple".to_owned()];
+    let err = builder.build().err();
+    assert_eq!(err, Some(NamedResourcesError::Unresolved(unresolved)));
+
+    // Errors are reset after `build`.
+    assert!(builder.resource("Step").build().is_ok());
+}
+#[test]
+fn missing_resource() {
+    let mut world = resource_world();
+    let resources = named_resources(&world, |b| {
+        b.resource("Step").resource("Missing");
+    });
+    let mut state = resources.state(&world);
+    let missing = world.components().resource_id::<Missing>().unwrap();
+
+    let err = state.get(&world).unwrap_err();
+    assert!(matches!(err, DynamicQueryError::MissingResource(id) if id == missing));
+
+    world.insert_resource(Missing);
+    assert_eq!(state.get(&world).unwrap().len(), 2);
+}
+#[test]
+fn system_with_resources() {
+    fn write_step(_: ResMut<Step>) {}
+
+    let mut world = resource_world();
+    world.insert_resource(Step(5));
+    let resources = named_resources(&world, |b| {
+        b.resource("Step");
+    });
+    let query = DynamicQuery::from_query::<Query<&mut TableRegSimple, ()>>(&mut world);
+    let system = DynamicSystem::new("add_step", vec![query], |queries, resources| {
+        let DynamicItem::Read(_, step) = resources[0] else {
+            panic!()
+        };
+        let step = step.downcast_ref::<Step>().unwrap().0;
+        for mut row in queries[0].iter_mut() {
+            let DynamicItem::Mut(_, simple) = &mut row[0] else {
+                panic!()
+            };
+            simple.downcast_mut::<TableRegSimple>().unwrap().x += step;
+        }
+    });
+    let system = system.with_resources(resources);
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems((system, write_step));
+    schedule.initialize(&mut world).unwrap();
+    let step = world.components().resource_id::<Step>().unwrap();
+    assert_eq!(schedule.graph().conflicting_systems()[0].2, [step]);
+
+    schedule.run(&mut world);
+    let mut simple = world.query::<&TableRegSimple>();
+    assert!(simple.iter(&world).all(|s| s.x == 5));
+}
@@ -0,0 +1,215 @@
+use std::any::TypeId;
+
+use bevy::prelude::*;
+use test_log::test;
+
+use super::*;
+use crate::builder::NamedDynamicBuilder;
+use crate::{Aggregate, AggregateError, DynamicItem, ExtractError, FetchKind, FieldError};
+
+fn row_world() -> (World, Entity) {
+    let mut world = registered_world();
+    world.init_component::<TableRegTag>();
+    let entity = world
+        .spawn((TableRegSimple { x: 1 }, SetRegSimple { x: 2 }))
+        .id();
+    (world, entity)
+}
+
+#[test]
+fn extract_row() {
+    let (mut world, entity) = row_world();
+    let reg = world.resource::<AppTypeRegistry>().clone();
+    let query = NamedDynamicBuilder::new(&reg.read(), world.components())
+        .entity()
+        .component("SetRegSimple")
+        .component_mut("TableRegSimple")
+        .optional("TableRegTag")
+        .build()
+        .unwrap();
+    let mut state = query.state(&mut world);
+
+    let mut row = state.get_mut(&mut world, entity).unwrap();
+    let extracted = row.extract::<(
+        &SetRegSimple,
+        &mut TableRegSimple,
+        Option<&TableRegTag>,
+        Entity,
+    )>();
+    let (set, table, tag, id) = extracted.unwrap();
+    assert_eq!((set.x, table.x, tag, id), (2, 1, None, entity));
+    table.x = 10;
+    assert_eq!(world.get::<TableRegSimple>(entity).unwrap().x, 10);
+
+    // Read-only items can't be extracted as `&mut`.
+    let mut row = state.get(&world, entity).unwrap();
+    let (table,) = row.extract::<(&TableRegSimple,)>().unwrap();
+    assert_eq!(table.x, 10);
+    let err = row.extract::<(&mut TableRegSimple,)>().err().unwrap();
+    assert!(matches!(err, ExtractError::Mismatch { found: "&T", .. }));
+
+    let err = row.extract::<(&SetRegTag,)>().err().unwrap();
+    assert!(matches!(err, ExtractError::Missing(_)));
+    // Each item can only be extracted once.
+    let err = row
+        .extract::<(&SetRegSimple, &SetRegSimple)>()
+        .err()
+        .unwrap();
+    assert!(matches!(err, ExtractError::Missing(_)));
+}
+#[test]
+fn row_lookup() {
+    let (mut world, entity) = row_world();
+    let reg = world.resource::<AppTypeRegistry>().clone();
+    let query = NamedDynamicBuilder::new(&reg.read(), world.components())
+        .entity()
+        .component("SetRegSimple")
+        .component_mut("TableRegSimple")
+        .optional("TableRegTag")
+        .build()
+        .unwrap();
+    let mut state = query.state(&mut world);
+    let set_id = world.component_id::<SetRegSimple>().unwrap();
+
+    let mut row = state.get_mut(&mut world, entity).unwrap();
+    let x = |value: Option<&dyn Reflect>| value.unwrap().downcast_ref::<SetRegSimple>().unwrap().x;
+    assert_eq!(x(row.get_by("SetRegSimple")), 2);
+    assert_eq!(x(row.get_by(std::any::type_name::<SetRegSimple>())), 2);
+    assert_eq!(x(row.get_by(set_id)), 2);
+    assert_eq!(x(row.get_by(TypeId::of::<SetRegSimple>())), 2);
+    assert_eq!(row.get::<SetRegSimple>().unwrap().x, 2);
+
+    // Not fetched, not present and not mutable.
+    assert!(row.get_by("SetRegTag").is_none());
+    assert!(row.get::<TableRegTag>().is_none());
+    assert!(row.get_mut::<SetRegSimple>().is_none());
+
+    row.get_mut::<TableRegSimple>().unwrap().x = 3;
+    let index = row.index_of("TableRegSimple").unwrap();
+    assert!(matches!(row[index], DynamicItem::Mut(..)));
+    assert_eq!(world.get::<TableRegSimple>(entity).unwrap().x, 3);
+}
+#[test]
+fn item_methods() {
+    let (mut world, entity) = row_world();
+    let reg = world.resource::<AppTypeRegistry>().clone();
+    let query = NamedDynamicBuilder::new(&reg.read(), world.components())
+        .entity()
+        .component("SetRegSimple")
+        .component_mut("TableRegSimple")
+        .optional_mut("TableRegTag")
+        .build()
+        .unwrap();
+    let mut state = query.state(&mut world);
+    let set_id = world.component_id::<SetRegSimple>();
+    let table_id = world.component_id::<TableRegSimple>();
+    let tag_id = world.component_id::<TableRegTag>();
+
+    let mut row = state.get_mut(&mut world, entity).unwrap();
+    let kinds: Vec<_> = row.iter().map(DynamicItem::kind).collect();
+    let expected = [
+        FetchKind::Entity,
+        FetchKind::Read,
+        FetchKind::Mut,
+        FetchKind::OptionMut,
+    ];
+    assert_eq!(kinds, expected);
+
+    assert_eq!(row[0].entity(), Some(entity));
+    assert!(row[0].as_reflect().is_none());
+    assert_eq!(row.component_id(0), None);
+
+    let set = &row[1];
+    assert!(!set.is_mutable() && set.is_present());
+    assert_eq!(set.downcast_ref::<SetRegSimple>().unwrap().x, 2);
+    let type_info = set.type_info().unwrap();
+    assert_eq!(type_info.type_id(), TypeId::of::<SetRegSimple>());
+    assert_eq!(row.component_id(1), set_id);
+
+    assert!(row[1].downcast_ref::<TableRegSimple>().is_none());
+    assert!(row[2].is_mutable());
+    row[2].downcast_mut::<TableRegSimple>().unwrap().x = 5;
+
+    let tag = &mut row[3];
+    assert!(tag.is_mutable() && !tag.is_present());
+    assert!(tag.as_reflect_mut().is_none());
+    assert!(tag.type_info().is_none());
+
+    let ids: Vec<_> = row.iter().map(DynamicItem::component_id).collect();
+    assert_eq!(ids, [None, set_id, table_id, tag_id]);
+}
+#[test]
+fn row_ambiguous_short_name() {
+    mod a {
+        use bevy::prelude::*;
+        #[derive(Component, Reflect, Default)]
+        #[reflect(Component)]
+        pub struct Dup {
+            pub x: usize,
+        }
+    }
+    mod b {
+        use bevy::prelude::*;
+        #[derive(Component, Reflect, Default)]
+        #[reflect(Component)]
+        pub struct Dup {
+            pub x: usize,
+        }
+    }
+    let mut world = registered_world();
+    {
+        let mut reg = world.resource::<AppTypeRegistry>().write();
+        reg.register::<a::Dup>();
+        reg.register::<b::Dup>();
+    }
+    let entity = world.spawn((a::Dup { x: 1 }, b::Dup { x: 2 })).id();
+    let query = DynamicQueryBuilder::new(&mut world)
+        .component::<a::Dup>()
+        .component::<b::Dup>()
+        .build()
+        .unwrap();
+    let mut state = query.state(&mut world);
+
+    let row = state.get(&world, entity).unwrap();
+    assert!(row.get_by("Dup").is_none());
+    let b_dup = row.get_by(std::any::type_name::<b::Dup>()).unwrap();
+    assert_eq!(b_dup.downcast_ref::<b::Dup>().unwrap().x, 2);
+
+    let err = state.aggregate(&world, Aggregate::Sum("Dup.x"));
+    let ambiguous = FieldError::Ambiguous("Dup".to_owned());
+    assert_eq!(err.err(), Some(AggregateError::Field(ambiguous)));
+    let a_dup = format!("{}.x", std::any::type_name::<a::Dup>());
+    let sum = state.aggregate(&world, Aggregate::Sum(&a_dup)).unwrap();
+    assert_eq!(sum.unwrap().downcast_ref::<usize>(), Some(&1));
+}
+#[test]
+fn row_index_many_components() {
+    use bevy_ecs::component::ComponentId;
+    use bevy_reflect::{FromType, ReflectFromPtr};
+
+    use crate::builder::{Fetch, FetchData, OrFilters};
+    use crate::row::RowIndex;
+
+    // More fetches than fit in a `u8`.
+    let from_ptr = <ReflectFromPtr as FromType<TableRegSimple>>::from_type();
+    let fetch = |i| {
+        Fetch::Read(FetchData {
+            id: ComponentId::new(i),
+            from_ptr: from_ptr.clone(),
+        })
+    };
+    let mut fetches: Vec<_> = (0..300).map(fetch).collect();
+    fetches.push(Fetch::Mut(FetchData {
+        id: ComponentId::new(300),
+        from_ptr,
+    }));
+    fetches.push(Fetch::Entity);
+    let query = DynamicQuery::new(fetches, OrFilters::default()).unwrap();
+
+    let index = RowIndex::new(&query.fetches);
+    assert_eq!(index.by_id(ComponentId::new(0)), Some(1));
+    assert_eq!(index.by_id(ComponentId::new(299)), Some(300));
+    assert_eq!(index.by_id(ComponentId::new(300)), Some(301));
+    assert_eq!(query.fetches.ids(Fetch::READ_IDX).count(), 300);
+    assert_eq!(query.fetches.ids(Fetch::MUT_IDX).count(), 1);
+}
@@ -0,0 +1,71 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use rand_core::{impls, RngCore};
+use test_log::test;
+
+use super::*;
+
+/// SplitMix64, good enough for tests.
+struct TestRng(u64);
+impl RngCore for TestRng {
+    fn next_u32(&mut self) -> u32 {
+        self.next_u64() as u32
+    }
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest);
+    }
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+fn check_sample(state: &mut crate::DynamicState, world: &World, rng: &mut TestRng) {
+    let mut seen = HashSet::new();
+    for _ in 0..50 {
+        let sample = state.sample(world, 5, rng);
+        let xs: HashSet<_> = sample
+            .iter()
+            .map(|row| simple_x(&row[0]).unwrap())
+            .collect();
+        assert_eq!(xs.len(), 5, "sampled entities are distinct");
+        seen.extend(xs);
+    }
+    assert_eq!(seen.len(), 30, "all entities can be sampled");
+    assert_eq!(state.sample(world, 100, rng).len(), 30);
+    assert!(state.choose(world, rng).is_some());
+}
+
+#[test]
+fn sample_exact() {
+    let (mut world, _) = simples_world(0..30, |x| x >= 20);
+    let query = DynamicQueryBuilder::new(&mut world)
+        .component::<TableRegSimple>()
+        .build()
+        .unwrap();
+    let mut state = query.state(&mut world);
+    check_sample(&mut state, &world, &mut TestRng(1));
+}
+
+#[test]
+fn sample_reservoir() {
+    let (mut world, _) = simples_world(0..30, |x| x >= 20);
+    let query = DynamicQueryBuilder::new(&mut world)
+        .component::<TableRegSimple>()
+        .or(|b| b.changed::<TableRegSimple>())
+        .build()
+        .unwrap();
+    let mut state = query.state(&mut world);
+    check_sample(&mut state, &world, &mut TestRng(2));
+
+    world.clear_trackers();
+    assert!(state.choose(&world, &mut TestRng(3)).is_none());
+}
@@ -0,0 +1,61 @@
+use bevy::ecs::change_detection::DetectChanges;
+use test_log::test;
+
+use super::*;
+use crate::{Snapshot, SnapshotRow};
+
+fn snapshot_x(row: &SnapshotRow) -> usize {
+    row.values[0]
+        .value
+        .downcast_ref::<TableRegSimple>()
+        .unwrap()
+        .x
+}
+
+#[test]
+fn snapshot_write_back() {
+    let (mut world, entities) = simples_world(0..4, |_| false);
+    let query = DynamicQueryBuilder::new(&mut world)
+        .component_mut::<TableRegSimple>()
+        .build()
+        .unwrap();
+    let mut state = query.state(&mut world);
+
+    let snapshot = state.snapshot(&world);
+    let xs: Vec<_> = snapshot.rows.iter().map(snapshot_x).collect();
+    let snapshot_entities: Vec<_> = snapshot.rows.iter().map(|r| r.entity).collect();
+    assert_eq!(xs, [0, 1, 2, 3]);
+    assert_eq!(snapshot_entities, entities);
+
+    for mut simple in world.query::<&mut TableRegSimple>().iter_mut(&mut world) {
+        simple.x += 10;
+    }
+    world.despawn(entities[1]);
+    world.entity_mut(entities[2]).remove::<TableRegSimple>();
+
+    let tick = world.increment_change_tick();
+    assert_eq!(state.apply_snapshot(&mut world, &snapshot), 2);
+
+    let restored = world
+        .entity(entities[0])
+        .get_ref::<TableRegSimple>()
+        .unwrap();
+    assert_eq!(restored.x, 0);
+    assert!(restored
+        .last_changed()
+        .is_newer_than(tick, world.read_change_tick()));
+    assert_eq!(world.get::<TableRegSimple>(entities[3]).unwrap().x, 3);
+    assert!(world.get::<TableRegSimple>(entities[2]).is_none());
+
+    // Read-only fetches are never written to.
+    let read_only = DynamicQueryBuilder::new(&mut world)
+        .component::<TableRegSimple>()
+        .build()
+        .unwrap();
+    let read_only = read_only.state(&mut world);
+    world.get_mut::<TableRegSimple>(entities[0]).unwrap().x = 20;
+    assert_eq!(read_only.apply_snapshot(&mut world, &snapshot), 0);
+    assert_eq!(world.get::<TableRegSimple>(entities[0]).unwrap().x, 20);
+
+    assert_eq!(state.apply_snapshot(&mut world, &Snapshot::default()), 0);
+}
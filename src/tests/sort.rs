@@ -0,0 +1,38 @@
+use bevy::prelude::*;
+use test_log::test;
+
+use super::*;
+use crate::builder::NamedDynamicBuilder;
+use crate::{FieldError, SortOrder};
+
+#[test]
+fn iter_sorted_by_field() {
+    let (mut world, _) = simples_world([4, 1, 3, 1, 0], |_| true);
+    world.spawn(SetRegTag);
+
+    let reg = world.resource::<AppTypeRegistry>().clone();
+    let query = NamedDynamicBuilder::new(&reg.read(), world.components())
+        .optional("TableRegSimple")
+        .entity()
+        .or(|b| b.with("SetRegTag"))
+        .build()
+        .unwrap();
+    let mut state = query.state(&mut world);
+
+    let sorted = state.iter_sorted_by(&world, "TableRegSimple.x", SortOrder::Ascending);
+    let rows: Vec<_> = sorted.unwrap().collect();
+    let xs: Vec<_> = rows.iter().map(|row| simple_x(&row[1])).collect();
+    assert_eq!(xs, [Some(0), Some(1), Some(1), Some(3), Some(4), None]);
+    // Ties are ordered by `Entity`.
+    assert!(rows[1][0].entity() < rows[2][0].entity());
+
+    let sorted = state.iter_sorted_by(&world, "TableRegSimple.x", SortOrder::Descending);
+    let xs: Vec<_> = sorted.unwrap().map(|row| simple_x(&row[1])).collect();
+    assert_eq!(xs, [Some(4), Some(3), Some(1), Some(1), Some(0), None]);
+
+    let err = state.iter_sorted_by(&world, "SetRegTag", SortOrder::Ascending);
+    assert_eq!(
+        err.err(),
+        Some(FieldError::NotFetched("SetRegTag".to_owned()))
+    );
+}
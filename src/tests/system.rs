@@ -0,0 +1,95 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::prelude::*;
+use test_log::test;
+
+use super::*;
+use crate::{DynamicItem, DynamicQuery, DynamicSystem};
+
+fn world_xs(world: &World) -> Vec<usize> {
+    let mut xs: Vec<_> = world
+        .iter_entities()
+        .filter_map(|e| e.get::<TableRegSimple>())
+        .map(|s| s.x)
+        .collect();
+    xs.sort_unstable();
+    xs
+}
+
+#[test]
+fn system_mutates_components() {
+    let mut world = test_world();
+    let query = DynamicQuery::from_query::<Query<&mut TableRegSimple, ()>>(&mut world);
+    let system = DynamicSystem::new("add_one", vec![query], |queries, _| {
+        for mut row in queries[0].iter_mut() {
+            let DynamicItem::Mut(_, simple) = &mut row[0] else {
+                panic!()
+            };
+            simple.downcast_mut::<TableRegSimple>().unwrap().x += 1;
+        }
+    });
+    let before = world_xs(&world);
+    assert!(!before.is_empty());
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(system);
+    schedule.run(&mut world);
+    schedule.run(&mut world);
+
+    let after = world_xs(&world);
+    let expected: Vec<_> = before.iter().map(|x| x + 2).collect();
+    assert_eq!(expected, after);
+}
+#[test]
+fn system_change_detection() {
+    let mut world = test_world();
+    let query =
+        DynamicQuery::from_query::<Query<&TableRegSimple, Changed<TableRegSimple>>>(&mut world);
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let system_seen = seen.clone();
+    let system = DynamicSystem::new("changed", vec![query], move |queries, _| {
+        let count = queries[0].iter().count();
+        system_seen.lock().unwrap().push(count);
+    });
+    let mut schedule = Schedule::default();
+    schedule.add_systems(system);
+
+    schedule.run(&mut world);
+    schedule.run(&mut world);
+    let mut simple = world.query::<&mut TableRegSimple>();
+    simple.iter_mut(&mut world).next().unwrap().x = 10;
+    schedule.run(&mut world);
+
+    let total = world.query::<&TableRegSimple>().iter(&world).count();
+    assert_eq!(*seen.lock().unwrap(), [total, 0, 1]);
+}
+#[test]
+fn scheduler_sees_conflicts() {
+    fn read_simple(_: Query<&TableRegSimple>) {}
+    fn read_fancy(_: Query<&TableRegFancy>) {}
+
+    let mut world = test_world();
+    let query = DynamicQuery::from_query::<Query<&mut TableRegSimple, ()>>(&mut world);
+    let system = DynamicSystem::new("write_simple", vec![query], |_, _| {});
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems((system, read_simple, read_fancy));
+    schedule.initialize(&mut world).unwrap();
+
+    let simple = world.component_id::<TableRegSimple>().unwrap();
+    let conflicts = schedule.graph().conflicting_systems();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].2, [simple]);
+}
+#[test]
+#[should_panic]
+fn conflicting_queries_in_system() {
+    let mut world = test_world();
+    let write = DynamicQuery::from_query::<Query<&mut TableRegSimple, ()>>(&mut world);
+    let read = DynamicQuery::from_query::<Query<&TableRegSimple, ()>>(&mut world);
+    let system = DynamicSystem::new("conflicting", vec![write, read], |_, _| {});
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(system);
+    schedule.run(&mut world);
+}
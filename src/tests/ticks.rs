@@ -0,0 +1,59 @@
+use bevy::prelude::*;
+use bevy_ecs::query::ReadOnlyWorldQuery;
+use test_log::test;
+
+use super::*;
+
+#[track_caller]
+fn test_same_count<F: DOr + ReadOnlyWorldQuery + 'static>(world: &mut World) {
+    let query = DynamicQuery::from_query::<Query<&TableRegSimple, F>>(world);
+    let mut state = query.state(world);
+    let dynamic_count = state.iter(world).count();
+    let entities: Vec<_> = state.iter(world).map(|_| ()).collect();
+    let typed_count = world
+        .query_filtered::<&TableRegSimple, F>()
+        .iter(world)
+        .count();
+    assert_eq!(dynamic_count, typed_count);
+    assert_eq!(entities.len(), typed_count);
+}
+#[test]
+fn disjunct_tick_filters() {
+    let mut world = test_world();
+    let simple = TableRegSimple::default;
+    let fancy = TableRegFancy::default;
+    let changed_fancy = world.spawn((simple(), SetRegTag, fancy())).id();
+    world.spawn((simple(), SetRegTag, fancy()));
+    let changed_simple = world.spawn((simple(), SetRegSimple::default())).id();
+    world.spawn((simple(), SetRegSimple::default()));
+    let both = (simple(), SetRegTag, SetRegSimple::default(), fancy());
+    let changed_both = world.spawn(both).id();
+    world.clear_trackers();
+
+    world
+        .get_mut::<TableRegFancy>(changed_fancy)
+        .unwrap()
+        .set_changed();
+    world
+        .get_mut::<TableRegSimple>(changed_simple)
+        .unwrap()
+        .set_changed();
+    world
+        .get_mut::<TableRegSimple>(changed_both)
+        .unwrap()
+        .set_changed();
+
+    test_same_count::<
+        Or<(
+            (With<SetRegTag>, Changed<TableRegFancy>),
+            (With<SetRegSimple>, Changed<TableRegSimple>),
+        )>,
+    >(&mut world);
+    test_same_count::<Or<(With<SetRegTag>, Changed<TableRegSimple>)>>(&mut world);
+    test_same_count::<
+        Or<(
+            Changed<TableRegFancy>,
+            (Without<SetRegTag>, Added<TableRegSimple>),
+        )>,
+    >(&mut world);
+}